@@ -1,6 +1,10 @@
 use std::fmt::{Debug, Display};
 use quickcheck::{Arbitrary, Gen};
 
+use crate::color::Color;
+use crate::piece::Piece;
+use crate::square::*;
+
 #[derive(PartialEq, Eq, Clone, Copy)]
 pub struct CastleRights {
     pub white_short: bool,
@@ -20,6 +24,42 @@ impl Default for CastleRights {
     }
 }
 
+impl CastleRights {
+    /// Take away whatever rights are lost when `piece` moves from `source` to `target`. The king
+    /// moving loses both, a rook leaving its home square loses that side's.
+    pub fn update(&mut self, piece: Piece, color: Color, source: Square, target: Square) {
+        match piece {
+            Piece::King => {
+                match color  {
+                    Color::WHITE => {
+                        self.white_short = false;
+                        self.white_long = false;
+                    }
+                    Color::BLACK => {
+                        self.black_short = false;
+                        self.black_long = false;
+                    }
+                }
+            }
+            Piece::Rook if source == H1 => { self.white_short = false; }
+            Piece::Rook if source == H8 => { self.black_short = false; }
+            Piece::Rook if source == A1 => { self.white_long = false; }
+            Piece::Rook if source == A8 => { self.black_long = false; }
+            _ => {}
+        }
+
+        // Anything landing on a rook's home square has either captured that rook, or the rook is
+        // long gone; either way, nobody is castling with it.
+        match target {
+            H1 => { self.white_short = false; }
+            A1 => { self.white_long = false; }
+            H8 => { self.black_short = false; }
+            A8 => { self.black_long = false; }
+            _ => {}
+        }
+    }
+}
+
 impl Arbitrary for CastleRights {
     fn arbitrary(g: &mut Gen) -> Self {
         CastleRights {
@@ -144,4 +184,24 @@ mod tests {
         };
         assert_eq!(rights.to_string(), "");
     }
+
+    #[test]
+    fn update_removes_the_rights_a_move_loses() {
+        let mut rights = CastleRights::default();
+        rights.update(Piece::Knight, Color::WHITE, G1, F3);
+        assert_eq!(rights.to_string(), "KQkq");
+
+        rights.update(Piece::Rook, Color::WHITE, A1, A4);
+        assert_eq!(rights.to_string(), "Kkq");
+
+        // A capture on h8 takes the rook, and black's short castle with it.
+        rights.update(Piece::Bishop, Color::WHITE, B2, H8);
+        assert_eq!(rights.to_string(), "Kq");
+
+        rights.update(Piece::King, Color::BLACK, E8, E7);
+        assert_eq!(rights.to_string(), "K");
+
+        rights.update(Piece::King, Color::WHITE, E1, G1);
+        assert_eq!(rights.to_string(), "");
+    }
 }
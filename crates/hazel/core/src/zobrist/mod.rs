@@ -199,7 +199,6 @@ impl Alter for Zobrist {
 
 
 impl Zobrist {
-    #[cfg(test)]
     pub fn inner(&self) -> u64 { self.0 }
 
     pub fn empty() -> Zobrist {
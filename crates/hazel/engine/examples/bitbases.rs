@@ -0,0 +1,36 @@
+//! Generates bitbases into a directory, ready to be pointed at with the `BitbasePath` option.
+//!
+//!     cargo run --release -p hazel-engine --example bitbases -- <dir> [KPK KRK KQK KBNK KBK KNK]
+//!
+//! With no signatures it does all of them. KBNK takes a couple of minutes, the rest are quick.
+
+use std::process::ExitCode;
+
+use hazel_engine::bitbase::{Bitbases, Signature};
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1);
+    let Some(dir) = args.next() else {
+        eprintln!("usage: bitbases <dir> [signature...]");
+        return ExitCode::FAILURE;
+    };
+
+    let mut signatures = vec![];
+    for name in args {
+        let Some(signature) = Signature::ALL.into_iter().find(|signature| signature.name().eq_ignore_ascii_case(&name)) else {
+            eprintln!("No bitbase called {}, try one of {:?}", name, Signature::ALL.map(|signature| signature.name()));
+            return ExitCode::FAILURE;
+        };
+        signatures.push(signature);
+    }
+    if signatures.is_empty() { signatures = Signature::ALL.to_vec(); }
+
+    let bitbases = Bitbases::generate(&signatures);
+    if let Err(e) = bitbases.save_dir(&dir) {
+        eprintln!("Couldn't save the bitbases to {}: {}", dir, e);
+        return ExitCode::FAILURE;
+    }
+
+    println!("Wrote {} bitbases to {}", bitbases.len(), dir);
+    ExitCode::SUCCESS
+}
@@ -0,0 +1,364 @@
+use hazel_bitboard::bitboard::Bitboard;
+use hazel_bitboard::constants::move_tables::KING_ATTACKS;
+use hazel_core::ben::BEN;
+use hazel_core::castle_rights::CastleRights;
+use hazel_core::color::Color;
+use hazel_core::interface::{Alter, Alteration, Query};
+use hazel_core::occupant::Occupant;
+use hazel_core::piece::Piece;
+use hazel_core::position_metadata::PositionMetadata;
+use hazel_core::square::Square;
+use hazel_generator::{GenerationMode, MoveGenerator};
+use hazel_representation::coup::rep::{Move, MoveType};
+use hazel_representation::game::position::Position;
+
+use super::Signature;
+
+/// Where everything is in a bitbase position.
+///
+/// The strong side (the one with pieces besides the king) is always white here, moving up the
+/// board, so positions where black is the strong side are flipped top to bottom first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Layout {
+    pub signature: Signature,
+    pub strong_to_move: bool,
+    /// The strong king, the weak king, then the strong side's pieces in `Signature::pieces` order.
+    /// Anything past those is unused.
+    pub squares: [Square; 4],
+}
+
+impl Layout {
+    /// The inverse of `index`.
+    pub fn decode(signature: Signature, mut index: usize) -> Self {
+        let mut squares = [Square::default(); 4];
+        for square in squares[..signature.men()].iter_mut().rev() {
+            *square = Square::new(index % 64);
+            index /= 64;
+        }
+
+        Self { signature, strong_to_move: index == 0, squares }
+    }
+
+    /// Where this position lives in its bitbase, side to move first, then each square in turn.
+    pub fn index(&self) -> usize {
+        self.squares[..self.signature.men()].iter()
+            .fold(!self.strong_to_move as usize, |index, square| index * 64 + square.index())
+    }
+
+    /// The layout of `position`, if it has the material of one of the bitbases.
+    pub fn of(position: &Position) -> Option<Self> {
+        let occupied = position.all_blockers();
+        if occupied.count() > 4 { return None; }
+
+        let mut kings = [None; 2];
+        let mut pieces = vec![];
+        for square in occupied {
+            let occupant = position.get(square);
+            let (Some(piece), Some(color)) = (occupant.piece(), occupant.color()) else { continue; };
+            match piece {
+                Piece::King => kings[color as usize] = Some(square),
+                _ => pieces.push((piece, color, square)),
+            }
+        }
+
+        let strong = pieces.first()?.1;
+        if pieces.iter().any(|(_, color, _)| *color != strong) { return None; }
+
+        let signature = Signature::ALL.into_iter().find(|signature| {
+            signature.pieces().len() == pieces.len() &&
+            signature.pieces().iter().all(|piece| pieces.iter().any(|(p, _, _)| p == piece))
+        })?;
+
+        // Flipping top to bottom turns black into white, and keeps pawns heading the right way.
+        let orient = |square: Square| if strong == Color::WHITE { square } else { Square::new(square.index() ^ 56) };
+
+        let mut squares = [Square::default(); 4];
+        squares[0] = orient(kings[strong as usize]?);
+        squares[1] = orient(kings[!strong as usize]?);
+        for (idx, piece) in signature.pieces().iter().enumerate() {
+            let (_, _, square) = pieces.iter().find(|(p, _, _)| p == piece)?;
+            squares[idx + 2] = orient(*square);
+        }
+
+        Some(Self { signature, strong_to_move: position.hero() == strong, squares })
+    }
+
+    pub fn strong_king(&self) -> Square {
+        self.squares[0]
+    }
+
+    pub fn weak_king(&self) -> Square {
+        self.squares[1]
+    }
+
+    /// The strong side's pieces besides the king, with their place in `squares`.
+    pub fn pieces(&self) -> impl Iterator<Item = (usize, Piece, Square)> + '_ {
+        self.signature.pieces().iter().enumerate().map(|(idx, piece)| (idx + 2, *piece, self.squares[idx + 2]))
+    }
+
+    pub fn occupancy(&self) -> Bitboard {
+        self.squares[..self.signature.men()].iter().fold(Bitboard::empty(), |board, square| board | Bitboard::from(*square))
+    }
+
+    /// Whether the same square is used twice, a pawn is on the first or last rank, the kings are
+    /// touching, or the weak king is in check with the strong side to move.
+    pub fn is_legal(&self) -> bool {
+        if self.occupancy().count() as usize != self.signature.men() { return false; }
+        if self.pieces().any(|(_, piece, square)| piece == Piece::Pawn && (square.rank() == 0 || square.rank() == 7)) { return false; }
+        if KING_ATTACKS[self.strong_king().index()].is_set(self.weak_king()) { return false; }
+
+        // The position only knows about checks against the side to move.
+        !self.strong_to_move || Layout { strong_to_move: false, ..*self }.position().checkers().is_empty()
+    }
+
+    /// The layout as a real position, white being the strong side.
+    pub fn position(&self) -> Position {
+        self.build(self.pieces())
+    }
+
+    fn build(&self, pieces: impl Iterator<Item = (usize, Piece, Square)>) -> Position {
+        let mut ben = BEN::empty();
+        ben.alter_mut(Alteration::place(self.strong_king(), Occupant::Occupied(Piece::King, Color::WHITE)));
+        ben.alter_mut(Alteration::place(self.weak_king(), Occupant::Occupied(Piece::King, Color::BLACK)));
+        for (_, piece, square) in pieces {
+            ben.alter_mut(Alteration::place(square, Occupant::Occupied(piece, Color::WHITE)));
+        }
+
+        ben.set_metadata(PositionMetadata {
+            side_to_move: if self.strong_to_move { Color::WHITE } else { Color::BLACK },
+            castling: CastleRights { white_short: false, white_long: false, black_short: false, black_long: false },
+            ..Default::default()
+        });
+        Position::new(ben)
+    }
+
+    /// How many ways the board can be flipped without changing what the position's worth. Without
+    /// pawns it can go either way or across the diagonal, with them only left to right.
+    pub fn reflections(&self) -> usize {
+        if self.signature.pieces().contains(&Piece::Pawn) { 2 } else { 8 }
+    }
+
+    fn reflected(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.reflections()).map(|reflection| {
+            let mut layout = *self;
+            for square in layout.squares[..self.signature.men()].iter_mut() {
+                *square = reflect(*square, reflection);
+            }
+            layout.index()
+        })
+    }
+
+    /// The smallest index among this layout's reflections, which all share a result.
+    pub fn canonical(&self) -> usize {
+        self.reflected().min().unwrap()
+    }
+
+    /// How many of the reflections leave the layout as it is, at least one (not flipping at all).
+    pub fn symmetries(&self) -> usize {
+        let index = self.index();
+        self.reflected().filter(|reflected| *reflected == index).count()
+    }
+
+    /// Every layout (with the weak side to move) where a weak king move leads here. Only makes
+    /// sense with the strong side to move.
+    pub fn weak_unmoves(&self, generator: &MoveGenerator) -> Vec<Layout> {
+        // King moves go both ways. With only the kings on the board, nothing stops the weak one
+        // stepping back to wherever it might have been in check from.
+        let kings = Layout { strong_to_move: false, ..*self }.build(std::iter::empty());
+        let occupancy = self.occupancy();
+
+        generator.generate(&kings, GenerationMode::Quiet).into_iter()
+            .filter(|mov| !occupancy.is_set(mov.target()))
+            .map(|mov| {
+                let mut layout = Layout { strong_to_move: false, ..*self };
+                layout.squares[1] = mov.target();
+                layout
+            }).collect()
+    }
+
+    /// Every layout (with the strong side to move) where a strong move leads here. Only makes
+    /// sense with the weak side to move.
+    ///
+    /// NOTE: Nothing ever gets taken back, so none of these are captures. Promotions come from a
+    /// different bitbase, see `Retrograde::promotes_to_win`.
+    pub fn strong_unmoves(&self, generator: &MoveGenerator) -> Vec<Layout> {
+        let strong = Layout { strong_to_move: true, ..*self };
+        let position = strong.position();
+        let mut unmoves = vec![];
+
+        // Everything but a pawn moves the same both ways, so anywhere it can go from here is
+        // somewhere it could have come from.
+        for mov in generator.generate(&position, GenerationMode::Quiet) {
+            let Some(idx) = self.squares[..self.signature.men()].iter().position(|square| *square == mov.source()) else { continue; };
+            if position.get(mov.source()).piece() == Some(Piece::Pawn) { continue; }
+
+            let mut layout = strong;
+            layout.squares[idx] = mov.target();
+            unmoves.push(layout);
+        }
+
+        // Pawns get put back a square or two and pushed again to see if that works.
+        for (idx, piece, square) in self.pieces() {
+            if piece != Piece::Pawn || square.rank() < 2 { continue; }
+
+            let froms = if square.rank() == 3 { vec![square.index() - 8, square.index() - 16] } else { vec![square.index() - 8] };
+            for from in froms.into_iter().map(Square::new) {
+                if self.occupancy().is_set(from) { continue; }
+
+                let mut layout = strong;
+                layout.squares[idx] = from;
+                if generator.is_legal(Move::new(from, square, MoveType::UCI_AMBIGUOUS), &layout.position()).is_ok() {
+                    unmoves.push(layout);
+                }
+            }
+        }
+        unmoves
+    }
+}
+
+/// Flips `square` left to right, top to bottom, then across the a1-h8 diagonal, going by the
+/// bits of `reflection`.
+fn reflect(square: Square, reflection: usize) -> Square {
+    let mut index = square.index();
+    if reflection & 1 != 0 { index ^= 7; }
+    if reflection & 2 != 0 { index ^= 56; }
+    if reflection & 4 != 0 { index = (index & 7) << 3 | index >> 3; }
+    Square::new(index)
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+
+    use super::*;
+
+    fn layout_of(fen: &str) -> Option<Layout> {
+        Layout::of(&Position::new(BEN::new(fen)))
+    }
+
+    #[test]
+    fn indices_round_trip() {
+        for signature in Signature::ALL {
+            for index in [0, 1, 4097, signature.positions() / 2 + 12345, signature.positions() - 1] {
+                assert_eq!(Layout::decode(signature, index).index(), index);
+            }
+        }
+    }
+
+    #[test]
+    fn finds_the_material() {
+        let krk = layout_of("8/8/8/4k3/8/8/8/R3K3 b - - 0 1").unwrap();
+        assert_eq!(krk.signature, Signature::KRK);
+        assert!(!krk.strong_to_move);
+        assert_eq!(krk.squares[..3], [E1, E5, A1]);
+
+        assert_eq!(layout_of("8/8/8/4k3/8/8/8/RN2K3 b - - 0 1"), None);
+        assert_eq!(layout_of("8/8/8/4k3/8/8/8/B3K1N1 w - - 0 1").unwrap().signature, Signature::KBNK);
+        assert_eq!(layout_of("8/8/8/4k3/8/8/8/4K3 w - - 0 1"), None);
+        assert_eq!(layout_of("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), None);
+    }
+
+    #[test]
+    fn black_is_flipped_to_white() {
+        let black = layout_of("4k3/8/3p4/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let white = layout_of("4k3/8/8/8/8/3P4/8/4K3 b - - 0 1").unwrap();
+
+        assert_eq!(black, white);
+        assert!(!black.strong_to_move);
+        assert_eq!(black.squares[..3], [E1, E8, D3]);
+    }
+
+    #[test]
+    fn legality() {
+        assert!(layout_of("8/8/8/4k3/8/8/8/R3K3 b - - 0 1").unwrap().is_legal());
+        // Black's in check with white to move.
+        assert!(!layout_of("8/8/8/4k3/8/8/8/4R1K1 w - - 0 1").unwrap().is_legal());
+        // The kings are touching.
+        assert!(!layout_of("8/8/8/8/8/8/4k3/R3K3 b - - 0 1").unwrap().is_legal());
+        // Two things on one square.
+        assert!(!Layout { signature: Signature::KRK, strong_to_move: true, squares: [E1, E5, E1, A1] }.is_legal());
+        // A pawn on the back rank.
+        assert!(!Layout { signature: Signature::KPK, strong_to_move: true, squares: [E1, E5, A8, A1] }.is_legal());
+    }
+
+    #[test]
+    fn reflections_share_an_index() {
+        let krk = |squares: [Square; 3]| Layout { signature: Signature::KRK, strong_to_move: false, squares: [squares[0], squares[1], squares[2], A1] };
+        assert_eq!(krk([E1, E5, A1]).canonical(), krk([E8, E4, A8]).canonical());
+        assert_eq!(krk([E1, E5, A1]).canonical(), krk([A5, E5, A1]).canonical());
+        assert_ne!(krk([E1, E5, A1]).canonical(), krk([E1, E5, B1]).canonical());
+
+        // Pawns only go one way, so they can only be flipped left to right.
+        let kpk = |squares: [Square; 3]| Layout { signature: Signature::KPK, strong_to_move: false, squares: [squares[0], squares[1], squares[2], A1] };
+        assert_eq!(kpk([E1, E5, D2]).canonical(), kpk([D1, D5, E2]).canonical());
+        assert_ne!(kpk([E1, E5, D2]).canonical(), kpk([E8, E4, D7]).canonical());
+    }
+
+    #[test]
+    fn unmoves() {
+        let generator = MoveGenerator::new();
+
+        // The pawn on e4 could have come from e3 or e2, the king from d1, d2, f1 or f2, and the rook
+        // from anywhere along its rank or file it can see.
+        let kpk = layout_of("8/8/8/8/4P3/8/8/4K2k b - - 0 1").unwrap();
+        let pawn_froms: Vec<Square> = kpk.strong_unmoves(&generator).iter().filter(|l| l.squares[0] == E1).map(|l| l.squares[2]).collect();
+        assert_eq!(pawn_froms.len(), 2);
+        assert!(pawn_froms.contains(&E3) && pawn_froms.contains(&E2));
+
+        let krk = layout_of("8/8/8/8/8/8/8/R3K2k b - - 0 1").unwrap();
+        assert_eq!(krk.strong_unmoves(&generator).len(), 5 + 10);
+        assert!(krk.strong_unmoves(&generator).iter().all(|layout| layout.strong_to_move));
+
+        let weak = layout_of("8/8/8/8/8/8/7k/R3K3 w - - 0 1").unwrap();
+        assert_eq!(weak.weak_unmoves(&generator).len(), 5);
+        assert!(weak.weak_unmoves(&generator).iter().all(|layout| !layout.strong_to_move));
+    }
+
+    #[test]
+    fn unmoves_undo_the_generators_moves() {
+        let generator = MoveGenerator::new();
+
+        // Every move that doesn't take or promote anything has to be undone by some unmove, and
+        // every unmove has to be a move.
+        for signature in Signature::ALL {
+            for index in (0..signature.positions()).step_by(signature.positions() / 500 + 1) {
+                let layout = Layout::decode(signature, index);
+                if !layout.is_legal() { continue; }
+
+                let mut position = layout.position();
+                for mov in generator.generate_moves(&position) {
+                    if mov.is_capture() || mov.is_promotion() { continue; }
+
+                    position.make(mov);
+                    let after = Layout::of(&position).unwrap();
+                    position.unmake();
+
+                    let unmoves = if after.strong_to_move { after.weak_unmoves(&generator) } else { after.strong_unmoves(&generator) };
+                    assert!(unmoves.contains(&layout), "{:?} isn't undone from {:?}", mov, BEN::from(after.position()));
+                }
+
+                let unmoves = if layout.strong_to_move { layout.weak_unmoves(&generator) } else { layout.strong_unmoves(&generator) };
+                for previous in unmoves.into_iter().filter(Layout::is_legal) {
+                    let mut position = previous.position();
+                    let leads_here = generator.generate_moves(&position).into_iter().any(|mov| {
+                        position.make(mov);
+                        let after = Layout::of(&position);
+                        position.unmake();
+                        after == Some(layout)
+                    });
+                    assert!(leads_here, "{:?} doesn't lead to {:?}", BEN::from(previous.position()), BEN::from(layout.position()));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn symmetries() {
+        let krk = |squares: [Square; 3]| Layout { signature: Signature::KRK, strong_to_move: false, squares: [squares[0], squares[1], squares[2], A1] };
+        assert_eq!(krk([E1, E5, A1]).symmetries(), 1);
+        // Everything on the long diagonal stays put when flipped across it.
+        assert_eq!(krk([A1, C3, H8]).symmetries(), 2);
+        assert_eq!(krk([A1, C3, H8]).reflections(), 8);
+    }
+}
@@ -0,0 +1,370 @@
+use std::fmt::{self, Debug, Display, Formatter};
+use std::ops::Neg;
+use std::path::Path;
+
+use hazel_core::piece::Piece;
+use hazel_representation::game::position::Position;
+
+mod layout;
+mod retrograde;
+
+use layout::Layout;
+
+/// What a bitbase position is worth, from the side to move's point of view.
+///
+/// NOTE: Ordered worst to best, so the best of a few results is just `max`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Wdl {
+    Loss,
+    Draw,
+    Win,
+}
+
+impl Neg for Wdl {
+    type Output = Wdl;
+
+    fn neg(self) -> Wdl {
+        match self {
+            Wdl::Loss => Wdl::Win,
+            Wdl::Draw => Wdl::Draw,
+            Wdl::Win => Wdl::Loss,
+        }
+    }
+}
+
+/// The material a bitbase covers, a king and these pieces against a lone king.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Signature {
+    KPK,
+    KRK,
+    KQK,
+    KBNK,
+    KBK,
+    KNK,
+}
+
+impl Signature {
+    /// NOTE: Bitbase files name their signature by its place in here, so new ones go on the end.
+    pub const ALL: [Signature; 6] = [Signature::KPK, Signature::KRK, Signature::KQK, Signature::KBNK, Signature::KBK, Signature::KNK];
+
+    /// The strong side's pieces, besides the king.
+    pub fn pieces(&self) -> &'static [Piece] {
+        match self {
+            Signature::KPK => &[Piece::Pawn],
+            Signature::KRK => &[Piece::Rook],
+            Signature::KQK => &[Piece::Queen],
+            Signature::KBNK => &[Piece::Bishop, Piece::Knight],
+            Signature::KBK => &[Piece::Bishop],
+            Signature::KNK => &[Piece::Knight],
+        }
+    }
+
+    /// How many pieces are on the board, kings included.
+    pub fn men(&self) -> usize {
+        2 + self.pieces().len()
+    }
+
+    /// How many positions the bitbase has room for, including the impossible ones.
+    pub fn positions(&self) -> usize {
+        2 * 64usize.pow(self.men() as u32)
+    }
+
+    /// The bitbases this one needs to be generated first, the ones a promotion can lead to.
+    pub fn dependencies(&self) -> &'static [Signature] {
+        match self {
+            Signature::KPK => &[Signature::KQK, Signature::KRK],
+            _ => &[],
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Signature::KPK => "KPK",
+            Signature::KRK => "KRK",
+            Signature::KQK => "KQK",
+            Signature::KBNK => "KBNK",
+            Signature::KBK => "KBK",
+            Signature::KNK => "KNK",
+        }
+    }
+
+    fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL.get(byte as usize).copied()
+    }
+}
+
+impl Display for Signature {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+#[derive(Debug)]
+pub enum BitbaseError {
+    Io(std::io::Error),
+    /// Doesn't start with `Bitbase::MAGIC`, or has a signature we don't know.
+    NotABitbase,
+    /// Too short (or too long) for the signature in its header.
+    Truncated(Signature),
+}
+
+impl Display for BitbaseError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            BitbaseError::Io(err) => write!(f, "bitbase: {}", err),
+            BitbaseError::NotABitbase => write!(f, "bitbase: not a bitbase file"),
+            BitbaseError::Truncated(signature) => write!(f, "bitbase: wrong size for {}", signature),
+        }
+    }
+}
+
+impl From<std::io::Error> for BitbaseError {
+    fn from(err: std::io::Error) -> Self {
+        BitbaseError::Io(err)
+    }
+}
+
+/// Win/draw/loss for every position of one signature, a bit per position.
+///
+/// A set bit is a win for the strong side, so it's a win if they're to move and a loss if not.
+/// Everything else (including the impossible positions) is a draw. Positions where black is the
+/// strong side are flipped to white first, so one table covers both.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Bitbase {
+    signature: Signature,
+    bits: Vec<u64>,
+}
+
+// Millions of bits aren't worth printing.
+impl Debug for Bitbase {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Bitbase({}, {} wins)", self.signature, self.bits.iter().map(|word| word.count_ones()).sum::<u32>())
+    }
+}
+
+impl Bitbase {
+    /// Starts every file, followed by the signature as one byte (its place in `Signature::ALL`),
+    /// then the bits as little-endian u64s.
+    pub const MAGIC: &'static [u8; 4] = b"HZBB";
+
+    /// Works the bitbase out from scratch. `others` must have everything in
+    /// `signature.dependencies()`, see `Bitbases::generate` for something that takes care of that.
+    pub fn generate(signature: Signature, others: &Bitbases) -> Self {
+        Self { signature, bits: retrograde::retrograde(signature, others) }
+    }
+
+    pub fn signature(&self) -> Signature {
+        self.signature
+    }
+
+    /// The result for `position`, if it has this bitbase's material.
+    pub fn probe(&self, position: &Position) -> Option<Wdl> {
+        let layout = Layout::of(position).filter(|layout| layout.signature == self.signature)?;
+        Some(self.probe_layout(&layout))
+    }
+
+    fn probe_layout(&self, layout: &Layout) -> Wdl {
+        match (self.is_decisive(layout.index()), layout.strong_to_move) {
+            (false, _) => Wdl::Draw,
+            (true, true) => Wdl::Win,
+            (true, false) => Wdl::Loss,
+        }
+    }
+
+    pub(crate) fn is_decisive(&self, index: usize) -> bool {
+        self.bits[index / 64] & (1 << (index % 64)) != 0
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BitbaseError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BitbaseError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BitbaseError> {
+        let header = Self::MAGIC.len() + 1;
+        if bytes.len() < header || &bytes[..Self::MAGIC.len()] != Self::MAGIC {
+            return Err(BitbaseError::NotABitbase);
+        }
+        let signature = Signature::from_byte(bytes[Self::MAGIC.len()]).ok_or(BitbaseError::NotABitbase)?;
+
+        let words = &bytes[header..];
+        if words.len() != signature.positions().div_ceil(64) * 8 {
+            return Err(BitbaseError::Truncated(signature));
+        }
+
+        let bits = words.chunks_exact(8).map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap())).collect();
+        Ok(Self { signature, bits })
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let signature = Signature::ALL.iter().position(|s| *s == self.signature).unwrap() as u8;
+
+        let mut bytes = Self::MAGIC.to_vec();
+        bytes.push(signature);
+        bytes.extend(self.bits.iter().flat_map(|word| word.to_le_bytes()));
+        bytes
+    }
+}
+
+/// A set of bitbases, probed by whichever matches the position.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Bitbases {
+    tables: Vec<Bitbase>,
+}
+
+impl Bitbases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generates each of `signatures`, and anything they depend on.
+    pub fn generate(signatures: &[Signature]) -> Self {
+        let mut bitbases = Self::new();
+        for signature in signatures {
+            bitbases.generate_one(*signature);
+        }
+        bitbases
+    }
+
+    fn generate_one(&mut self, signature: Signature) {
+        if self.get(signature).is_some() { return; }
+
+        for dependency in signature.dependencies() {
+            self.generate_one(*dependency);
+        }
+
+        tracing::debug!("Generating the {} bitbase", signature);
+        let bitbase = Bitbase::generate(signature, self);
+        self.insert(bitbase);
+    }
+
+    /// Adds `bitbase`, replacing any other with the same signature.
+    pub fn insert(&mut self, bitbase: Bitbase) {
+        self.tables.retain(|table| table.signature != bitbase.signature);
+        self.tables.push(bitbase);
+    }
+
+    pub fn get(&self, signature: Signature) -> Option<&Bitbase> {
+        self.tables.iter().find(|table| table.signature == signature)
+    }
+
+    pub fn len(&self) -> usize {
+        self.tables.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+
+    /// The result for `position`, if we have a bitbase for its material.
+    pub fn probe(&self, position: &Position) -> Option<Wdl> {
+        let layout = Layout::of(position)?;
+        Some(self.get(layout.signature)?.probe_layout(&layout))
+    }
+
+    fn file_name(signature: Signature) -> String {
+        format!("{}.bitbase", signature)
+    }
+
+    /// Loads every bitbase in `dir`, named as `save_dir` names them. Missing ones are skipped.
+    pub fn load_dir<P: AsRef<Path>>(dir: P) -> Result<Self, BitbaseError> {
+        let mut bitbases = Self::new();
+        for signature in Signature::ALL {
+            let path = dir.as_ref().join(Self::file_name(signature));
+            if !path.exists() { continue; }
+            bitbases.insert(Bitbase::load(path)?);
+        }
+        Ok(bitbases)
+    }
+
+    pub fn save_dir<P: AsRef<Path>>(&self, dir: P) -> Result<(), BitbaseError> {
+        std::fs::create_dir_all(&dir)?;
+        for table in &self.tables {
+            table.save(dir.as_ref().join(Self::file_name(table.signature)))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use std::sync::LazyLock;
+
+    use hazel_core::ben::BEN;
+
+    use super::*;
+
+    // KPK brings KQK and KRK along with it. The other tests use these too, rather than each
+    // generating their own.
+    pub(crate) static BITBASES: LazyLock<Bitbases> = LazyLock::new(|| Bitbases::generate(&[Signature::KPK]));
+
+    fn probe(fen: &str) -> Option<Wdl> {
+        BITBASES.probe(&Position::new(BEN::new(fen)))
+    }
+
+    #[test]
+    fn generates_the_dependencies() {
+        assert_eq!(BITBASES.len(), 3);
+        assert!(BITBASES.get(Signature::KBNK).is_none());
+    }
+
+    #[test]
+    fn kqk_and_krk_are_won() {
+        assert_eq!(probe("8/8/8/4k3/8/8/8/1Q2K3 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("8/8/8/4k3/8/8/8/R3K3 b - - 0 1"), Some(Wdl::Loss));
+        // Unless the rook's hanging.
+        assert_eq!(probe("8/8/8/8/8/8/3kR3/7K b - - 0 1"), Some(Wdl::Draw));
+        // Or it's stalemate.
+        assert_eq!(probe("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1"), Some(Wdl::Draw));
+        // Black's the one with the queen here.
+        assert_eq!(probe("3qk3/8/8/8/8/8/8/4K3 w - - 0 1"), Some(Wdl::Loss));
+    }
+
+    #[test]
+    fn kpk() {
+        // The king's on the sixth in front of its pawn, which wins whoever's to move.
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1"), Some(Wdl::Win));
+        assert_eq!(probe("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1"), Some(Wdl::Loss));
+        // Stalemate.
+        assert_eq!(probe("4k3/4P3/4K3/8/8/8/8/8 b - - 0 1"), Some(Wdl::Draw));
+        // Rook pawns don't win with the king in the corner.
+        assert_eq!(probe("k7/8/K7/P7/8/8/8/8 w - - 0 1"), Some(Wdl::Draw));
+        // The square, black's king can't catch the pawn from h5, but can from f6.
+        assert_eq!(probe("8/8/8/7k/P7/8/8/K7 b - - 0 1"), Some(Wdl::Loss));
+        assert_eq!(probe("8/8/5k2/8/P7/8/8/K7 b - - 0 1"), Some(Wdl::Draw));
+        // Same for black's pawns.
+        assert_eq!(probe("7k/8/8/p7/7K/8/8/8 w - - 0 1"), Some(Wdl::Loss));
+    }
+
+    #[test]
+    fn only_probes_what_it_has() {
+        assert_eq!(probe("8/8/8/4k3/8/8/8/B3K1N1 w - - 0 1"), None);
+        assert_eq!(probe("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), None);
+        assert_eq!(probe("4k3/8/8/8/8/8/8/4K3 w - - 0 1"), None);
+    }
+
+    #[test]
+    fn round_trips_through_bytes() {
+        let krk = BITBASES.get(Signature::KRK).unwrap();
+        assert_eq!(&Bitbase::from_bytes(&krk.to_bytes()).unwrap(), krk);
+
+        assert!(matches!(Bitbase::from_bytes(b"nope"), Err(BitbaseError::NotABitbase)));
+        assert!(matches!(Bitbase::from_bytes(b"HZBB\x02\x00"), Err(BitbaseError::Truncated(Signature::KQK))));
+    }
+
+    #[test]
+    fn round_trips_through_a_directory() {
+        let dir = std::env::temp_dir().join(format!("hazel-bitbases-{}", std::process::id()));
+        BITBASES.save_dir(&dir).unwrap();
+        let loaded = Bitbases::load_dir(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        let loaded = loaded.unwrap();
+        for signature in [Signature::KPK, Signature::KQK, Signature::KRK] {
+            assert_eq!(loaded.get(signature), BITBASES.get(signature));
+        }
+    }
+}
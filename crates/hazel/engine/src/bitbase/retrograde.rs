@@ -0,0 +1,226 @@
+use hazel_core::piece::Piece;
+use hazel_generator::{GenerationMode, MoveGenerator};
+
+use super::layout::Layout;
+use super::{Bitbases, Signature, Wdl};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// Not a real position, see `Layout::is_legal`.
+    Illegal,
+    /// Nothing's known yet. If it stays that way, it's a draw.
+    Unknown,
+    /// A win with the strong side to move, or a loss with the weak side to move.
+    Decisive,
+    /// Stalemate, or the weak king can take something, neither of which can be a loss.
+    Drawn,
+}
+
+/// Works backwards from mate to find every position the strong side wins.
+///
+/// Reflections of a position are all worth the same, so only the smallest of each (see
+/// `Layout::canonical`) is worked out, and the rest copy it at the end. Forwards and backwards both
+/// go through `MoveGenerator`, see `Layout::strong_unmoves`.
+struct Retrograde<'a> {
+    signature: Signature,
+    generator: MoveGenerator,
+    state: Vec<State>,
+    /// For each position with the weak side to move, how many of its moves haven't yet been shown
+    /// to lose, times the number of reflections so `propagate` never has to deal in fractions.
+    moves_left: Vec<u8>,
+    /// Newly decided positions whose predecessors haven't been looked at yet.
+    queue: Vec<usize>,
+    /// Where promotions end up, if there are any.
+    others: &'a Bitbases,
+}
+
+/// One bit per position in `signature`'s bitbase, set when the position is decisive. `others`
+/// must have everything the signature depends on.
+pub(super) fn retrograde(signature: Signature, others: &Bitbases) -> Vec<u64> {
+    let mut retrograde = Retrograde {
+        signature,
+        generator: MoveGenerator::new(),
+        state: vec![State::Illegal; signature.positions()],
+        moves_left: vec![0; signature.positions()],
+        queue: vec![],
+        others,
+    };
+
+    retrograde.classify();
+    retrograde.propagate();
+
+    let mut bits = vec![0; signature.positions().div_ceil(64)];
+    for index in 0..signature.positions() {
+        let canonical = Layout::decode(signature, index).canonical();
+        if retrograde.state[canonical] == State::Decisive {
+            bits[index / 64] |= 1 << (index % 64);
+        }
+    }
+    bits
+}
+
+impl Retrograde<'_> {
+    /// Finds everything that can be decided on the spot: mates, stalemates, captures, and
+    /// promotions into a won position.
+    fn classify(&mut self) {
+        for index in 0..self.signature.positions() {
+            let layout = Layout::decode(self.signature, index);
+            if layout.canonical() != index || !layout.is_legal() { continue; }
+            self.state[index] = State::Unknown;
+
+            if layout.strong_to_move {
+                if self.promotes_to_win(&layout) { self.decide(index); }
+                continue;
+            }
+
+            let position = layout.position();
+            let moves = self.generator.generate_moves(&position);
+            if moves.iter().any(|mov| mov.is_capture()) {
+                self.state[index] = State::Drawn;
+            } else if moves.is_empty() && position.checkers().is_nonempty() {
+                self.decide(index);
+            } else if moves.is_empty() {
+                self.state[index] = State::Drawn;
+            } else {
+                self.moves_left[index] = (moves.len() * layout.reflections()) as u8;
+            }
+        }
+    }
+
+    /// Works back from each decided position. A strong side win makes each move into it one less
+    /// way out for the weak side, and once it has none left, every strong move into that wins.
+    ///
+    /// NOTE: Only the canonical positions are decided, so an unmove stands in for the moves between
+    /// every reflection of the two positions. Going by how many reflections each has, a
+    /// predecessor `p` of a decided `d` loses `|reflections of d| * symmetries(p)` from
+    /// `moves_left`, which comes to its number of moves into `d` (or any of its reflections) times
+    /// the scale `moves_left` started at.
+    fn propagate(&mut self) {
+        while let Some(index) = self.queue.pop() {
+            let layout = Layout::decode(self.signature, index);
+
+            if layout.strong_to_move {
+                let reflections = layout.reflections() / layout.symmetries();
+                for previous in layout.weak_unmoves(&self.generator) {
+                    let canonical = previous.canonical();
+                    if self.state[canonical] != State::Unknown { continue; }
+
+                    self.moves_left[canonical] -= (reflections * previous.symmetries()) as u8;
+                    if self.moves_left[canonical] == 0 { self.decide(canonical); }
+                }
+            } else {
+                for previous in layout.strong_unmoves(&self.generator) {
+                    let canonical = previous.canonical();
+                    if self.state[canonical] == State::Unknown { self.decide(canonical); }
+                }
+            }
+        }
+    }
+
+    fn decide(&mut self, index: usize) {
+        self.state[index] = State::Decisive;
+        self.queue.push(index);
+    }
+
+    /// Whether a pawn can promote into a position where the weak side loses. A lone bishop or
+    /// knight can't mate, so underpromotions are never it.
+    fn promotes_to_win(&self, layout: &Layout) -> bool {
+        if !layout.pieces().any(|(_, piece, square)| piece == Piece::Pawn && square.rank() == 6) { return false; }
+
+        let mut position = layout.position();
+        self.generator.generate(&position, GenerationMode::Tactical).into_iter().filter(|mov| mov.is_promotion()).any(|mov| {
+            position.make(mov);
+            let won = self.others.probe(&position) == Some(Wdl::Loss);
+            position.unmake();
+            won
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_representation::game::position::Position;
+
+    use super::*;
+    use crate::bitbase::tests::BITBASES;
+
+    /// What a position is worth after the move, as far as the side which just moved is
+    /// concerned, by looking it up wherever the material says it should be.
+    fn after(bitbases: &Bitbases, position: &Position) -> Wdl {
+        match bitbases.probe(position) {
+            Some(wdl) => -wdl,
+            // Took something, or made a bishop or knight, either way nobody can win.
+            None => Wdl::Draw,
+        }
+    }
+
+    /// Checks every `step`th position against what the generator says the moves are: a position
+    /// is won if some move leads to a loss, lost if every move leads to a win (or it's mate),
+    /// and drawn otherwise.
+    fn check_against_the_generator(bitbases: &Bitbases, signature: Signature, step: usize) {
+        let generator = MoveGenerator::new();
+        let mut checked = 0;
+
+        for index in (0..signature.positions()).step_by(step) {
+            let layout = Layout::decode(signature, index);
+            if !layout.is_legal() { continue; }
+
+            let mut position = layout.position();
+            let expected = bitbases.probe(&position).unwrap();
+
+            let moves = generator.generate_moves(&position);
+            let results: Vec<Wdl> = moves.iter().map(|mov| {
+                position.make(*mov);
+                let result = after(bitbases, &position);
+                position.unmake();
+                result
+            }).collect();
+
+            let actual = if moves.is_empty() {
+                if position.checkers().is_nonempty() { Wdl::Loss } else { Wdl::Draw }
+            } else {
+                results.into_iter().max().unwrap()
+            };
+
+            assert_eq!(actual, expected, "{:?}", BEN::from(position));
+            checked += 1;
+        }
+
+        assert!(checked > 0);
+    }
+
+    #[test]
+    fn krk_agrees_with_the_generator() {
+        check_against_the_generator(&BITBASES, Signature::KRK, 997);
+    }
+
+    #[test]
+    fn kqk_agrees_with_the_generator() {
+        check_against_the_generator(&BITBASES, Signature::KQK, 997);
+    }
+
+    #[test]
+    fn kpk_agrees_with_the_generator() {
+        check_against_the_generator(&BITBASES, Signature::KPK, 499);
+    }
+
+    // NOTE: KBNK is 64 times the size of the others, which is the best part of an hour without
+    // optimizations. These have the same two pieces in the same machinery on a smaller table, and
+    // `Layout`'s tests check the unmoves for KBNK itself.
+    #[test]
+    fn kbk_and_knk_agree_with_the_generator() {
+        let bitbases = Bitbases::generate(&[Signature::KBK, Signature::KNK]);
+        let probe = |fen: &str| bitbases.probe(&Position::new(BEN::new(fen)));
+
+        // Neither can mate, even with the weak king in the corner.
+        assert_eq!(probe("7k/8/5K2/5B2/8/8/8/8 w - - 0 1"), Some(Wdl::Draw));
+        assert_eq!(probe("7k/8/5K2/8/8/8/8/N7 b - - 0 1"), Some(Wdl::Draw));
+        for signature in [Signature::KBK, Signature::KNK] {
+            assert!(bitbases.get(signature).unwrap().bits.iter().all(|word| *word == 0));
+        }
+
+        check_against_the_generator(&bitbases, Signature::KBK, 97);
+        check_against_the_generator(&bitbases, Signature::KNK, 97);
+    }
+}
@@ -0,0 +1,145 @@
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+use hazel_core::ben::BEN;
+use hazel_generator::MoveGenerator;
+use hazel_parser::pgn::PGN;
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+use hazel_representation::game::reason::Reason;
+
+use super::{encode_move, polyglot_key, Book, BookEntry};
+
+/// Builds a book out of played games.
+///
+/// Each time a move is played it scores for the side which played it, two points if they went on
+/// to win, one for a draw (or a game with no result), and none for a loss. The weight is the total,
+/// so a move which only ever lost doesn't make it into the book.
+#[derive(Debug, Default, Clone)]
+pub struct BookBuilder {
+    /// Points for each (key, move).
+    scores: HashMap<(u64, u16), u64>,
+    /// Only this many plies from the start of each game go in, if set.
+    depth: Option<usize>,
+}
+
+impl BookBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_depth(mut self, plies: usize) -> Self {
+        self.depth = Some(plies);
+        self
+    }
+
+    /// Adds the mainline of the game, variations are ignored.
+    pub fn add_pgn(&mut self, pgn: &PGN) -> &mut Self {
+        let (start, moves) = pgn.mainline();
+        self.add_game(start, &moves, pgn.result())
+    }
+
+    pub fn add_game(&mut self, start: impl Into<BEN>, moves: &[Move], result: Option<Reason>) -> &mut Self {
+        let generator = MoveGenerator::new();
+        let mut position = Position::new(start);
+
+        for mov in moves.iter().take(self.depth.unwrap_or(usize::MAX)) {
+            // Gets us the move exactly as the generator would have it, so castling and so on are
+            // encoded properly.
+            let Ok(mov) = generator.is_legal(*mov, &position) else {
+                tracing::error!("Illegal move {} in game, skipping the rest", mov.to_uci());
+                break;
+            };
+
+            let points = match result.and_then(|reason| reason.winner()) {
+                Some(winner) if winner == position.hero() => 2,
+                Some(_) => 0,
+                None => 1,
+            };
+            *self.scores.entry((polyglot_key(&position), encode_move(mov))).or_default() += points;
+
+            position.make(mov);
+        }
+
+        self
+    }
+
+    pub fn build(&self) -> Book {
+        // Weights are only 16 bits, so if the most popular move won't fit, everything is scaled
+        // down to match.
+        let most = self.scores.values().copied().max().unwrap_or(0);
+        let scale = most.div_ceil(u16::MAX as u64).max(1);
+
+        let mut entries: Vec<BookEntry> = self.scores.iter()
+            .filter(|(_, score)| **score > 0)
+            .map(|(&(key, mov), score)| BookEntry { key, mov, weight: (score / scale).max(1) as u16, learn: 0 })
+            .collect();
+
+        // Polyglot books list the best moves first for each position.
+        entries.sort_by_key(|entry| (entry.key, Reverse(entry.weight), entry.mov));
+        Book::new(entries)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::color::Color;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    fn start() -> Position {
+        Position::new(BEN::start_position())
+    }
+
+    #[test]
+    fn weights_follow_the_results() {
+        let e4 = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+        let d4 = Move::new(D2, D4, MoveType::DOUBLE_PAWN);
+        let c4 = Move::new(C2, C4, MoveType::DOUBLE_PAWN);
+
+        let book = BookBuilder::new()
+            .add_game(BEN::start_position(), &[e4], Some(Reason::Checkmate(Color::WHITE)))
+            .add_game(BEN::start_position(), &[e4], Some(Reason::Draw))
+            .add_game(BEN::start_position(), &[d4], None)
+            .add_game(BEN::start_position(), &[c4], Some(Reason::Resignation(Color::BLACK)))
+            .build();
+
+        assert_eq!(book.moves(&start()), vec![(e4, 3), (d4, 1)]);
+    }
+
+    #[test]
+    fn depth_limits_the_plies() {
+        let moves = [Move::new(E2, E4, MoveType::DOUBLE_PAWN), Move::new(E7, E5, MoveType::DOUBLE_PAWN)];
+        let book = BookBuilder::new().with_depth(1).add_game(BEN::start_position(), &moves, None).build();
+
+        assert_eq!(book.len(), 1);
+    }
+
+    #[test]
+    fn builds_from_pgn() {
+        let pgn = PGN::load("../../../tests/fixtures/with-variations-halts.pgn").unwrap();
+        let book = BookBuilder::new().add_pgn(&pgn).build();
+
+        // Black won, so only black's moves are in, and none of the variations.
+        assert_eq!(book.len(), 7);
+        assert!(book.moves(&start()).is_empty());
+
+        let after_d4 = Position::with_moves(BEN::start_position(), vec![Move::new(D2, D4, MoveType::DOUBLE_PAWN)]);
+        assert_eq!(book.moves(&after_d4), vec![(Move::new(D7, D5, MoveType::DOUBLE_PAWN), 2)]);
+    }
+
+    #[test]
+    fn builds_a_file_we_can_load() {
+        let pgn = PGN::load("../../../tests/fixtures/no-variations-and-halts.pgn").unwrap();
+        let book = BookBuilder::new().with_depth(12).add_pgn(&pgn).build();
+
+        let path = std::env::temp_dir().join(format!("hazel-book-{}.bin", std::process::id()));
+        book.save(&path).unwrap();
+        let loaded = Book::load(&path);
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(loaded.unwrap(), book);
+    }
+}
@@ -0,0 +1,231 @@
+use std::fmt::{self, Debug, Display, Formatter};
+use std::path::Path;
+
+use rand::Rng;
+
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+mod builder;
+mod polyglot;
+
+pub use builder::*;
+pub use polyglot::*;
+
+/// One record of a Polyglot book, stored as 16 big-endian bytes.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct BookEntry {
+    /// The `polyglot_key` of the position
+    pub key: u64,
+    /// The move, see `encode_move`
+    pub mov: u16,
+    /// How often to play this move, relative to the other moves from the same position. Zero
+    /// means never.
+    pub weight: u16,
+    /// Unused by us, but kept so we write back what we read.
+    pub learn: u32,
+}
+
+impl BookEntry {
+    pub const SIZE: usize = 16;
+
+    pub fn from_bytes(bytes: &[u8; Self::SIZE]) -> Self {
+        Self {
+            key: u64::from_be_bytes(bytes[0..8].try_into().unwrap()),
+            mov: u16::from_be_bytes(bytes[8..10].try_into().unwrap()),
+            weight: u16::from_be_bytes(bytes[10..12].try_into().unwrap()),
+            learn: u32::from_be_bytes(bytes[12..16].try_into().unwrap()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> [u8; Self::SIZE] {
+        let mut bytes = [0; Self::SIZE];
+        bytes[0..8].copy_from_slice(&self.key.to_be_bytes());
+        bytes[8..10].copy_from_slice(&self.mov.to_be_bytes());
+        bytes[10..12].copy_from_slice(&self.weight.to_be_bytes());
+        bytes[12..16].copy_from_slice(&self.learn.to_be_bytes());
+        bytes
+    }
+}
+
+#[derive(Debug)]
+pub enum BookError {
+    Io(std::io::Error),
+    /// The file isn't a whole number of entries, holds the length of the file.
+    Truncated(usize),
+}
+
+impl Display for BookError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            BookError::Io(err) => write!(f, "book: {}", err),
+            BookError::Truncated(len) => write!(f, "book: {} bytes is not a whole number of entries", len),
+        }
+    }
+}
+
+impl From<std::io::Error> for BookError {
+    fn from(err: std::io::Error) -> Self {
+        BookError::Io(err)
+    }
+}
+
+/// A Polyglot opening book, entries sorted by key so all the moves for a position can be found by
+/// binary search.
+///
+/// NOTE: The whole file is read into memory. Books are at most a few hundred MB, and usually much
+/// smaller, so I haven't bothered with seeking around the file.
+#[derive(Default, Clone, PartialEq)]
+pub struct Book {
+    entries: Vec<BookEntry>,
+}
+
+// The entries are a lot to print, and not very interesting.
+impl Debug for Book {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        write!(f, "Book({} entries)", self.entries.len())
+    }
+}
+
+impl Book {
+    pub fn new(mut entries: Vec<BookEntry>) -> Self {
+        // Stable, so moves for the same position keep their order.
+        entries.sort_by_key(|entry| entry.key);
+        Self { entries }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, BookError> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<(), BookError> {
+        Ok(std::fs::write(path, self.to_bytes())?)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BookError> {
+        if !bytes.len().is_multiple_of(BookEntry::SIZE) {
+            return Err(BookError::Truncated(bytes.len()));
+        }
+
+        // Books should already be sorted, but it's cheap to make sure.
+        Ok(Self::new(bytes.chunks_exact(BookEntry::SIZE).map(|chunk| BookEntry::from_bytes(chunk.try_into().unwrap())).collect()))
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.entries.iter().flat_map(|entry| entry.to_bytes()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Every entry for the position with this key.
+    pub fn entries_for(&self, key: u64) -> &[BookEntry] {
+        let start = self.entries.partition_point(|entry| entry.key < key);
+        let end = self.entries.partition_point(|entry| entry.key <= key);
+        &self.entries[start..end]
+    }
+
+    /// The book moves for `position` with their weights. Anything which isn't legal here (a key
+    /// collision, or a broken book) is left out.
+    pub fn moves(&self, position: &Position) -> Vec<(Move, u16)> {
+        self.entries_for(polyglot_key(position)).iter()
+            .filter_map(|entry| decode_move(position, entry.mov).map(|mov| (mov, entry.weight)))
+            .collect()
+    }
+
+    /// Picks one of the book moves at random, in proportion to their weights.
+    pub fn pick(&self, position: &Position, rng: &mut impl Rng) -> Option<Move> {
+        let moves = self.moves(position);
+        let total: u32 = moves.iter().map(|(_, weight)| *weight as u32).sum();
+        if total == 0 { return None; }
+
+        let mut roll = rng.random_range(0..total);
+        for (mov, weight) in moves {
+            if roll < weight as u32 { return Some(mov); }
+            roll -= weight as u32;
+        }
+        unreachable!("The roll is less than the total weight")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    use super::*;
+
+    fn start() -> Position {
+        Position::new(BEN::start_position())
+    }
+
+    fn entry(mov: Move, weight: u16) -> BookEntry {
+        BookEntry { key: polyglot_key(&start()), mov: encode_move(mov), weight, learn: 0 }
+    }
+
+    #[test]
+    fn entries_round_trip_through_bytes() {
+        let entry = BookEntry { key: 0x463b96181691fc9c, mov: 0x031c, weight: 7, learn: 0xdeadbeef };
+        let bytes = entry.to_bytes();
+        assert_eq!(&bytes[..8], &[0x46, 0x3b, 0x96, 0x18, 0x16, 0x91, 0xfc, 0x9c]);
+        assert_eq!(BookEntry::from_bytes(&bytes), entry);
+    }
+
+    #[test]
+    fn books_round_trip_through_bytes() {
+        let book = Book::new(vec![
+            BookEntry { key: 3, ..Default::default() },
+            BookEntry { key: 1, mov: 1, ..Default::default() },
+            BookEntry { key: 1, mov: 2, ..Default::default() },
+        ]);
+        assert_eq!(Book::from_bytes(&book.to_bytes()).unwrap(), book);
+        assert!(matches!(Book::from_bytes(&[0; 17]), Err(BookError::Truncated(17))));
+    }
+
+    #[test]
+    fn finds_every_entry_for_a_key() {
+        let book = Book::new((0..10).map(|key| BookEntry { key: key / 3, mov: key as u16, ..Default::default() }).collect());
+        assert_eq!(book.entries_for(1).iter().map(|entry| entry.mov).collect::<Vec<_>>(), vec![3, 4, 5]);
+        assert_eq!(book.entries_for(3).len(), 1);
+        assert!(book.entries_for(4).is_empty());
+    }
+
+    #[test]
+    fn only_legal_moves_come_out() {
+        let e4 = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+        let book = Book::new(vec![entry(e4, 1), entry(Move::new(E2, E5, MoveType::QUIET), 1)]);
+        assert_eq!(book.moves(&start()), vec![(e4, 1)]);
+    }
+
+    #[test]
+    fn picks_in_proportion_to_weight() {
+        let e4 = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+        let d4 = Move::new(D2, D4, MoveType::DOUBLE_PAWN);
+        let a3 = Move::new(A2, A3, MoveType::QUIET);
+        let book = Book::new(vec![entry(e4, 3), entry(d4, 1), entry(a3, 0)]);
+
+        let mut rng = StdRng::seed_from_u64(0x1F2E3D4C);
+        let picks: Vec<Move> = (0..400).filter_map(|_| book.pick(&start(), &mut rng)).collect();
+        let e4s = picks.iter().filter(|mov| **mov == e4).count();
+
+        assert_eq!(picks.len(), 400);
+        assert!(!picks.contains(&a3));
+        assert!((250..350).contains(&e4s), "{} e4s", e4s);
+    }
+
+    #[test]
+    fn no_moves_out_of_book() {
+        let book = Book::new(vec![entry(Move::new(A2, A3, MoveType::QUIET), 0)]);
+        let mut rng = StdRng::seed_from_u64(0);
+        assert_eq!(book.pick(&start(), &mut rng), None);
+        assert_eq!(Book::default().pick(&start(), &mut rng), None);
+    }
+}
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+
+use witch::{MessageFor, Witch};
+use crate::driver::hazel::{Hazel, HazelResponse};
+use crate::evaluation::Evaluation;
+
+/// Asks for the static evaluation of the current position, e.g. for the UI to show.
+pub struct GetEvaluation;
+
+#[async_trait]
+impl<const BUF_SIZE: usize> MessageFor<Witch<BUF_SIZE, Hazel, HazelResponse>> for GetEvaluation {
+    async fn run(&self, witch: &mut Witch<BUF_SIZE, Hazel, HazelResponse>) {
+        witch.write(HazelResponse::Evaluation(witch.state.position.as_ref().map(Evaluation::new)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::constants::START_POSITION_FEN;
+    use witch::WitchHandle;
+
+    use super::*;
+    use crate::evaluation::TEMPO;
+    use crate::uci::UCIMessage;
+
+    #[tokio::test]
+    async fn evaluates_the_current_position() {
+        let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+        w.send(Box::new(GetEvaluation)).await;
+        assert_eq!(w.read().await, Some(HazelResponse::Evaluation(None)));
+
+        w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+        w.send(Box::new(GetEvaluation)).await;
+        match w.read().await {
+            Some(HazelResponse::Evaluation(Some(eval))) => assert_eq!(eval.score(), TEMPO),
+            other => panic!("Expected an evaluation, got {:?}", other),
+        }
+    }
+}
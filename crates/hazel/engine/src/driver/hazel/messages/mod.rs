@@ -2,9 +2,13 @@ mod initialization;
 mod uci_message;
 mod get_state;
 mod get_position;
+mod get_evaluation;
+mod search;
 
 
 pub use initialization::*;
 pub use get_state::*;
 pub use get_position::*;
+pub use get_evaluation::*;
+pub use search::*;
 
@@ -0,0 +1,57 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use async_trait::async_trait;
+
+use witch::{MessageFor, Witch};
+use crate::driver::hazel::{Hazel, HazelResponse, State};
+use crate::uci::UCIMessage;
+
+/// The search runs on its own thread, and sends these back to the actor to pass on (`info` lines).
+pub struct SearchOutput(pub UCIMessage);
+
+#[async_trait]
+impl<const BUF_SIZE: usize> MessageFor<Witch<BUF_SIZE, Hazel, HazelResponse>> for SearchOutput {
+    async fn run(&self, witch: &mut Witch<BUF_SIZE, Hazel, HazelResponse>) {
+        witch.write(HazelResponse::UCIResponse(self.0.clone()));
+    }
+}
+
+/// The last thing a search sends, its `bestmove`. Holds that search's stop flag, so we can tell
+/// whether it's the one we're still waiting on.
+pub struct SearchFinished(pub Arc<AtomicBool>, pub UCIMessage);
+
+#[async_trait]
+impl<const BUF_SIZE: usize> MessageFor<Witch<BUF_SIZE, Hazel, HazelResponse>> for SearchFinished {
+    async fn run(&self, witch: &mut Witch<BUF_SIZE, Hazel, HazelResponse>) {
+        let SearchFinished(stop, best_move) = self;
+        if witch.state.state == State::Quitting { return; }
+
+        match witch.state.search.as_mut() {
+            Some(search) if search.owns(stop) => {
+                if search.must_wait() {
+                    tracing::debug!("Search finished early, holding the bestmove until it's asked for");
+                    search.held = Some(Box::new(best_move.clone()));
+                    return;
+                }
+                witch.state.search = None;
+            },
+            // An older search, replaced by a `go` before it finished. The GUI still gets its answer.
+            _ => {}
+        }
+
+        witch.write(HazelResponse::UCIResponse(best_move.clone()));
+    }
+}
+
+/// Sent to ourselves when a search's hard time limit is up. Holds that search's stop flag, so a
+/// deadline which arrives after its search has finished can't stop a later one.
+pub struct Deadline(pub Arc<AtomicBool>);
+
+#[async_trait]
+impl<const BUF_SIZE: usize> MessageFor<Witch<BUF_SIZE, Hazel, HazelResponse>> for Deadline {
+    async fn run(&self, _witch: &mut Witch<BUF_SIZE, Hazel, HazelResponse>) {
+        tracing::debug!("Out of time, stopping the search");
+        self.0.store(true, Ordering::Relaxed);
+    }
+}
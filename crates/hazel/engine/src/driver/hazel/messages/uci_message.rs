@@ -1,11 +1,22 @@
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::AtomicBool;
+
 use async_trait::async_trait;
 
+use hazel_generator::MoveGenerator;
 use hazel_parser::uci::UCI;
 use hazel_core::ben::BEN;
 use witch::{MessageFor, Witch};
+use hazel_representation::coup::rep::Move;
 use hazel_representation::game::position::Position;
-use crate::uci::UCIMessage;
-use crate::driver::hazel::{Hazel, HazelResponse};
+use crate::bitbase::Bitbases;
+use crate::book::Book;
+use crate::search::{Search, TimeManager, DEFAULT_HASH_MB, MAX_PLY};
+use crate::uci::{GoParams, UCIMessage};
+use crate::driver::hazel::{options, BackgroundSearch, Deadline, Hazel, HazelResponse, SearchFinished, SearchOutput, State};
+
+/// How deep to search when `go` sets no limits at all.
+const DEFAULT_DEPTH: usize = 4;
 
 #[async_trait]
 impl<const BUF_SIZE: usize> MessageFor<Witch<BUF_SIZE, Hazel, HazelResponse>> for UCIMessage {
@@ -36,26 +47,219 @@ impl<const BUF_SIZE: usize> MessageFor<Witch<BUF_SIZE, Hazel, HazelResponse>> fo
         match self {
             UCIMessage::UCI => {
                 witch.write(HazelResponse::UCIResponse(UCIMessage::ID("hazel".to_string(), "0.1".to_string())));
+                for option in options().iter() {
+                    witch.write(HazelResponse::UCIResponse(UCIMessage::Option(option.clone())));
+                }
+                witch.write(HazelResponse::UCIResponse(UCIMessage::UCIOk));
             },
             UCIMessage::IsReady => {
                 witch.write(HazelResponse::UCIResponse(UCIMessage::ReadyOk));
             },
             UCIMessage::SetOption(name, value) => {
-                witch.state.options.insert(name.clone(), value.clone());
+                let options = options();
+                let (option, value) = match options.coerce(name, value.as_deref()) {
+                    Ok(coerced) => coerced,
+                    Err(err) => {
+                        tracing::error!("{}, ignoring", err);
+                        return;
+                    }
+                };
+
+                match option.name() {
+                    "Hash" => {
+                        let megabytes = value.as_ref().and_then(|v| v.parse().ok()).unwrap_or(DEFAULT_HASH_MB);
+                        witch.state.table.resize(megabytes);
+                    },
+                    "Clear Hash" => witch.state.table.clear(),
+                    "Book" => {
+                        let path = value.as_deref().unwrap_or_default();
+                        if path.is_empty() {
+                            witch.state.book = None;
+                        } else {
+                            match Book::load(path) {
+                                Ok(book) => witch.state.book = Some(Arc::new(book)),
+                                Err(err) => {
+                                    tracing::error!("{}, ignoring", err);
+                                    return;
+                                }
+                            }
+                        }
+                    },
+                    "BitbasePath" => {
+                        let path = value.as_deref().unwrap_or_default();
+                        if path.is_empty() {
+                            witch.state.bitbases = None;
+                        } else {
+                            match Bitbases::load_dir(path) {
+                                Ok(bitbases) if bitbases.is_empty() => {
+                                    tracing::warn!("No bitbases in {}, ignoring", path);
+                                    return;
+                                },
+                                Ok(bitbases) => witch.state.bitbases = Some(Arc::new(bitbases)),
+                                Err(err) => {
+                                    tracing::error!("{}, ignoring", err);
+                                    return;
+                                }
+                            }
+                        }
+                    },
+                    _ => {}
+                }
+
+                if value.is_some() {
+                    witch.state.options.insert(option.name().to_string(), value);
+                }
             },
             UCIMessage::UCINewGame => {
+                witch.state.table.clear();
             },
             UCIMessage::Position(fen, moves) => {
-                let moves = moves.iter().map(|m| UCI::try_from(m).unwrap().into()).collect();
-                let ben = BEN::new(fen);
+                let generator = MoveGenerator::new();
+                let mut position = Position::new(BEN::new(fen));
+
+                for m in moves {
+                    let Ok(uci) = UCI::try_from(m) else {
+                        tracing::error!("Could not parse move {:?}, ignoring the rest", m);
+                        break;
+                    };
+
+                    match generator.is_legal(uci.into(), &position) {
+                        Ok(mov) => position.make(mov),
+                        Err(reason) => {
+                            tracing::error!("Illegal move {:?} ({:?}), ignoring the rest", m, reason);
+                            break;
+                        }
+                    }
+                }
+
+                witch.state.position = Some(position);
+            },
+            UCIMessage::Go(args) => {
+                let params = match GoParams::try_from(args.as_slice()) {
+                    Ok(params) => params,
+                    Err(err) => {
+                        tracing::error!("{}, ignoring", err);
+                        return;
+                    }
+                };
+
+                let Some(mut position) = witch.state.position.clone() else {
+                    tracing::error!("Go without a position, nothing to search");
+                    witch.write(HazelResponse::UCIResponse(UCIMessage::BestMove("0000".to_string(), None)));
+                    return;
+                };
+
+                // Pondering and infinite searches have to wait for the GUI, and `searchmoves` might
+                // rule the book out, so the book is only for plain old `go`.
+                let plain = !params.ponder && !params.infinite && params.searchmoves.is_empty();
+                if plain && witch.state.option("OwnBook").as_deref() == Some("true") {
+                    if let Some(mov) = witch.state.book.as_ref().and_then(|book| book.pick(&position, &mut rand::rng())) {
+                        witch.write(HazelResponse::UCIResponse(UCIMessage::BestMove(mov.to_uci(), None)));
+                        return;
+                    }
+                }
+
+                let generator = MoveGenerator::new();
+                let root_moves : Vec<Move> = params.searchmoves.iter().filter_map(|mov| {
+                    generator.is_legal(*mov, &position)
+                        .inspect_err(|reason| tracing::error!("searchmoves: {} is illegal ({:?}), skipping", mov.to_uci(), reason))
+                        .ok()
+                }).collect();
+                // An empty list means everything to the search, so if none of them were legal say
+                // so, rather than quietly searching moves we weren't asked about.
+                if root_moves.is_empty() && !params.searchmoves.is_empty() {
+                    tracing::error!("searchmoves: none of them are legal, searching every move instead");
+                }
+
+                // While pondering we're on the opponent's clock, so the limits are worked out now
+                // but don't start until `ponderhit`.
+                let mut time = TimeManager::new(&GoParams { ponder: false, ..params.clone() }, position.hero());
+                let ponderhit = params.ponder.then(|| Arc::new(OnceLock::new()));
+                if let Some(ponderhit) = &ponderhit {
+                    time = time.with_ponder(ponderhit.clone());
+                }
+
+                let depth = params.depth.unwrap_or(
+                    if time.is_limited() || params.infinite || params.nodes.is_some() { MAX_PLY } else { DEFAULT_DEPTH }
+                );
+
+                let stop = Arc::new(AtomicBool::new(false));
+                let mut background = BackgroundSearch::new(stop.clone(), params.infinite);
+                match ponderhit {
+                    Some(ponderhit) => background = background.pondering(ponderhit, time.hard_limit()),
+                    // The hard limit comes back round as a message, so it doesn't depend on the
+                    // search keeping an eye on the clock, or the GUI remembering to send `stop`.
+                    None => if let Some(hard) = time.hard_limit() {
+                        witch.send_after(hard, Box::new(Deadline(stop.clone())));
+                    }
+                }
+
+                if let Some(previous) = witch.state.search.replace(background) {
+                    // The GUI shouldn't do this, but if it does, the newest `go` wins.
+                    tracing::warn!("Go while already searching, stopping the previous search");
+                    previous.stop();
+                    if let Some(held) = previous.held {
+                        witch.write(HazelResponse::UCIResponse(*held));
+                    }
+                }
 
-                witch.state.position = Some(Position::with_moves(ben, moves));
+                let mut search = Search::with_table(witch.state.table.clone())
+                    .with_root_moves(root_moves)
+                    .with_time(time)
+                    .with_node_limit(params.nodes)
+                    .with_bitbases(witch.state.bitbases.clone())
+                    .with_multi_pv(witch.state.option("MultiPV").and_then(|v| v.parse().ok()).unwrap_or(1))
+                    .with_stop(stop.clone());
+
+                let sase = witch.sase();
+                std::thread::spawn(move || {
+                    let result = search.run(&mut position, depth, |iteration| {
+                        // Nobody listening just means we're shutting down.
+                        _ = sase.blocking_send(Box::new(SearchOutput(iteration.info())));
+                    });
+
+                    let best_move = result.best_move().map_or("0000".to_string(), |mov| mov.to_uci());
+                    let ponder = result.ponder_move().map(|mov| mov.to_uci());
+                    if sase.blocking_send(Box::new(SearchFinished(stop, UCIMessage::BestMove(best_move, ponder)))).is_err() {
+                        tracing::error!("Search finished, but nobody is listening");
+                    }
+                });
+            },
+            UCIMessage::Stop => {
+                let Some(search) = witch.state.search.as_mut() else {
+                    tracing::debug!("Stop, but nothing is running");
+                    return;
+                };
+
+                // Either the search sends its `bestmove` as soon as it notices, or it's already
+                // finished and we've been sitting on it.
+                search.stop();
+                if let Some(held) = search.held.take() {
+                    witch.state.search = None;
+                    witch.write(HazelResponse::UCIResponse(*held));
+                }
             },
-            UCIMessage::Go(_) => {
-                // for now, we will just statically 'search' by replying with a 'bestmove' based on
-                // a random move, right now stubbing in a null move with no ponder
-                witch.write(HazelResponse::UCIResponse(UCIMessage::Info(vec!["Status".to_string(), "WIP".to_string()])));
-                witch.write(HazelResponse::UCIResponse(UCIMessage::BestMove("0000".to_string(), None)));
+            UCIMessage::PonderHit => {
+                let Some(search) = witch.state.search.as_mut().filter(|search| search.is_pondering()) else {
+                    tracing::debug!("Ponderhit, but we weren't pondering");
+                    return;
+                };
+
+                // Carry on with the same search, but now the clock's running.
+                search.ponderhit();
+                let (stop, hard_limit) = (search.stop.clone(), search.hard_limit);
+                if let Some(held) = search.held.take() {
+                    witch.state.search = None;
+                    witch.write(HazelResponse::UCIResponse(*held));
+                } else if let Some(hard) = hard_limit {
+                    witch.send_after(hard, Box::new(Deadline(stop)));
+                }
+            },
+            UCIMessage::Quit => {
+                if let Some(search) = witch.state.search.take() {
+                    search.stop();
+                }
+                witch.state.state = State::Quitting;
             },
             _ => {
                 tracing::error!("Unsupported UCI Message: {:?}", self);
@@ -71,8 +275,11 @@ mod tests {
 
     mod uci_messages {
         use crate::driver::hazel::GetState;
+        use crate::search::MAX_MULTI_PV;
         use witch::WitchHandle;
         use hazel_core::constants::START_POSITION_FEN;
+        use hazel_core::square::*;
+        use hazel_representation::coup::rep::{Move, MoveType};
 
         use super::*;
 
@@ -84,6 +291,22 @@ mod tests {
             let result = w.read().await;
 
             assert_eq!(result, Some(HazelResponse::UCIResponse(UCIMessage::ID("hazel".to_string(), "0.1".to_string()))));
+
+            let mut advertised = vec![];
+            while let Some(HazelResponse::UCIResponse(UCIMessage::Option(option))) = w.read().await {
+                advertised.push(option.to_string());
+                if advertised.len() == options().iter().count() { break; }
+            }
+            assert_eq!(advertised, vec![
+                "option name Hash type spin default 16 min 1 max 4096",
+                "option name Clear Hash type button",
+                "option name Ponder type check default false",
+                "option name MultiPV type spin default 1 min 1 max 64",
+                "option name OwnBook type check default false",
+                "option name Book type string default <empty>",
+                "option name BitbasePath type string default <empty>",
+            ]);
+            assert_eq!(w.read().await, Some(HazelResponse::UCIResponse(UCIMessage::UCIOk)));
         }
 
         #[tokio::test]
@@ -100,15 +323,96 @@ mod tests {
         async fn set_option() {
             let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
 
-            w.send(Box::new(UCIMessage::SetOption("name".to_string(), Some("value".to_string())))).await;
+            w.send(Box::new(UCIMessage::SetOption("multipv".to_string(), Some("3".to_string())))).await;
             w.send(Box::new(GetState)).await;
             if let Some(HazelResponse::Debug(result)) = w.read().await {
-                assert_eq!(result.options.get("name"), Some(&Some("value".to_string())));
+                assert_eq!(result.options.get("MultiPV"), Some(&Some("3".to_string())));
+                assert_eq!(result.option("MultiPV"), Some("3".to_string()));
+                assert_eq!(result.option("Ponder"), Some("false".to_string()));
             } else {
                 panic!("Expected Debug response");
             }
         }
 
+        #[tokio::test]
+        async fn bad_options_are_ignored() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::SetOption("name".to_string(), Some("value".to_string())))).await;
+            w.send(Box::new(UCIMessage::SetOption("Ponder".to_string(), Some("maybe".to_string())))).await;
+            w.send(Box::new(UCIMessage::SetOption("MultiPV".to_string(), None))).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            assert!(result.options.is_empty());
+            assert_eq!(result.option("name"), None);
+        }
+
+        #[tokio::test]
+        async fn spin_options_are_clamped() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::SetOption("MultiPV".to_string(), Some("1000".to_string())))).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            assert_eq!(result.option("MultiPV"), Some(MAX_MULTI_PV.to_string()));
+        }
+
+        #[tokio::test]
+        async fn clear_hash_clears_the_table() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "1".to_string()]))).await;
+            w.read().await;
+            w.read().await;
+
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            let start = result.position.clone().unwrap().zobrist().position;
+            assert!(result.table.probe(start).is_some());
+
+            w.send(Box::new(UCIMessage::SetOption("Clear Hash".to_string(), None))).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            assert!(result.table.probe(start).is_none());
+            assert!(!result.options.contains_key("Clear Hash"));
+        }
+
+        #[tokio::test]
+        async fn hash_resizes_the_table() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::SetOption("Hash".to_string(), Some("1".to_string())))).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            let small = result.table.capacity();
+
+            w.send(Box::new(UCIMessage::SetOption("Hash".to_string(), Some("4".to_string())))).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            assert_eq!(result.table.capacity(), small * 4);
+        }
+
+        #[tokio::test]
+        async fn ucinewgame_clears_the_table() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "1".to_string()]))).await;
+            w.read().await;
+            w.read().await;
+
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            let start = result.position.clone().unwrap().zobrist().position;
+            assert!(result.table.probe(start).is_some());
+
+            w.send(Box::new(UCIMessage::UCINewGame)).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            assert!(result.table.probe(start).is_none());
+        }
+
         #[tokio::test]
         async fn position() {
             let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
@@ -121,5 +425,414 @@ mod tests {
                 panic!("Expected Debug response");
             }
         }
+
+        #[tokio::test]
+        async fn position_stops_at_an_illegal_move() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            let moves = vec!["e2e4".to_string(), "e7e5".to_string(), "e1e3".to_string(), "d7d5".to_string()];
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), moves))).await;
+            w.send(Box::new(GetState)).await;
+            if let Some(HazelResponse::Debug(result)) = w.read().await {
+                let expected = Position::with_moves(BEN::new(START_POSITION_FEN), vec![
+                    Move::new(E2, E4, MoveType::DOUBLE_PAWN),
+                    Move::new(E7, E5, MoveType::DOUBLE_PAWN),
+                ]);
+                assert_eq!(result.position.unwrap(), expected);
+            } else {
+                panic!("Expected Debug response");
+            }
+        }
+
+        #[tokio::test]
+        async fn position_keeps_underpromotions() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            let fen = "7k/4P3/8/8/8/8/8/K7 w - - 0 1";
+            w.send(Box::new(UCIMessage::Position(fen.to_string(), vec!["e7e8n".to_string()]))).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(result)) = w.read().await else { panic!("Expected Debug response"); };
+            let expected = Position::with_moves(BEN::new(fen), vec![Move::new(E7, E8, MoveType::PROMOTION_KNIGHT)]);
+            assert_eq!(result.position.unwrap(), expected);
+        }
+
+        #[tokio::test]
+        async fn go_reports_each_depth_then_a_legal_bestmove() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec!["e2e4".to_string()]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "2".to_string()]))).await;
+
+            for depth in 1..=2 {
+                match w.read().await {
+                    Some(HazelResponse::UCIResponse(UCIMessage::Info(info))) => {
+                        assert_eq!(info.depth, Some(depth));
+                    },
+                    other => panic!("Expected Info, got {:?}", other),
+                }
+            }
+
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(best_move, Some(_)))) = w.read().await else {
+                panic!("Expected BestMove with a ponder move");
+            };
+
+            let position = Position::with_moves(BEN::new(START_POSITION_FEN), vec![Move::new(E2, E4, MoveType::DOUBLE_PAWN)]);
+            let legal = MoveGenerator::new().generate_moves(&position);
+            assert!(legal.iter().any(|mov| mov.to_uci() == best_move));
+        }
+
+        #[tokio::test]
+        async fn multi_pv_reports_each_line() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::SetOption("multipv".to_string(), Some("3".to_string())))).await;
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "1".to_string()]))).await;
+
+            for k in 1..=3 {
+                let Some(HazelResponse::UCIResponse(UCIMessage::Info(info))) = w.read().await else { panic!("Expected Info"); };
+                assert_eq!((info.depth, info.multipv), (Some(1), Some(k)));
+            }
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(_, _))) = w.read().await else { panic!("Expected BestMove"); };
+        }
+
+        #[tokio::test]
+        async fn go_only_searches_searchmoves() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            // Taking the queen is obviously best, but we've been told to look at something else.
+            w.send(Box::new(UCIMessage::Position("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1".to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go("searchmoves e1f1 d2d3 depth 1".split(' ').map(String::from).collect()))).await;
+
+            w.read().await;
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(best_move, _))) = w.read().await else {
+                panic!("Expected BestMove");
+            };
+            assert!(best_move == "e1f1" || best_move == "d2d3");
+        }
+
+        #[tokio::test]
+        async fn go_searches_everything_if_no_searchmoves_are_legal() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1".to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go("searchmoves e1e3 d2e3 depth 1".split(' ').map(String::from).collect()))).await;
+
+            w.read().await;
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(best_move, _))) = w.read().await else {
+                panic!("Expected BestMove");
+            };
+            assert_eq!(best_move, "d2d5");
+        }
+
+        #[tokio::test]
+        async fn malformed_go_is_ignored() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "lots".to_string()]))).await;
+            w.send(Box::new(UCIMessage::IsReady)).await;
+            assert_eq!(w.read().await, Some(HazelResponse::UCIResponse(UCIMessage::ReadyOk)));
+        }
+
+        #[tokio::test]
+        async fn go_with_a_clock_stops_in_time() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            let start = std::time::Instant::now();
+            w.send(Box::new(UCIMessage::Go("movetime 200".split(' ').map(String::from).collect()))).await;
+
+            loop {
+                match w.read().await {
+                    Some(HazelResponse::UCIResponse(UCIMessage::Info(_))) => continue,
+                    Some(HazelResponse::UCIResponse(UCIMessage::BestMove(best_move, _))) => {
+                        assert_ne!(best_move, "0000");
+                        break;
+                    }
+                    other => panic!("Expected Info or BestMove, got {:?}", other),
+                }
+            }
+
+            // Give a debug build plenty of slack, it just mustn't run on to the next depth limit.
+            assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        }
+
+        #[tokio::test]
+        async fn the_witch_keeps_working_while_searching() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go("movetime 500".split(' ').map(String::from).collect()))).await;
+            w.send(Box::new(UCIMessage::IsReady)).await;
+
+            // `info` lines may come first, but the actor doesn't wait for the search to finish.
+            loop {
+                match w.read().await {
+                    Some(HazelResponse::UCIResponse(UCIMessage::Info(_))) => continue,
+                    Some(HazelResponse::UCIResponse(UCIMessage::ReadyOk)) => break,
+                    other => panic!("Expected ReadyOk before anything else, got {:?}", other),
+                }
+            }
+        }
+
+        // Skips `info` lines until something else turns up.
+        async fn next_non_info(w: &WitchHandle<10, Hazel, HazelResponse>) -> Option<HazelResponse> {
+            loop {
+                match w.read().await {
+                    Some(HazelResponse::UCIResponse(UCIMessage::Info(_))) => continue,
+                    other => return other,
+                }
+            }
+        }
+
+        #[tokio::test]
+        async fn stop_ends_an_infinite_search() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["infinite".to_string()]))).await;
+            w.send(Box::new(UCIMessage::IsReady)).await;
+            assert_eq!(next_non_info(&w).await, Some(HazelResponse::UCIResponse(UCIMessage::ReadyOk)));
+
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(state)) = next_non_info(&w).await else { panic!("Expected Debug response"); };
+            assert!(state.is_searching());
+
+            w.send(Box::new(UCIMessage::Stop)).await;
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(best_move, _))) = next_non_info(&w).await else {
+                panic!("Expected BestMove");
+            };
+            assert_ne!(best_move, "0000");
+
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(state)) = next_non_info(&w).await else { panic!("Expected Debug response"); };
+            assert!(!state.is_searching());
+        }
+
+        #[tokio::test]
+        async fn infinite_search_waits_for_stop_even_when_done() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            // Mate in one, so the search is over after the first iteration.
+            w.send(Box::new(UCIMessage::Position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["infinite".to_string()]))).await;
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+            w.send(Box::new(UCIMessage::IsReady)).await;
+            assert_eq!(next_non_info(&w).await, Some(HazelResponse::UCIResponse(UCIMessage::ReadyOk)));
+
+            w.send(Box::new(UCIMessage::Stop)).await;
+            assert_eq!(next_non_info(&w).await, Some(HazelResponse::UCIResponse(UCIMessage::BestMove("a1a8".to_string(), None))));
+        }
+
+        #[tokio::test]
+        async fn stop_without_a_search_is_ignored() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Stop)).await;
+            w.send(Box::new(UCIMessage::IsReady)).await;
+            assert_eq!(w.read().await, Some(HazelResponse::UCIResponse(UCIMessage::ReadyOk)));
+        }
+
+        #[tokio::test]
+        async fn quit_stops_the_search_without_a_bestmove() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go(vec!["infinite".to_string()]))).await;
+            w.send(Box::new(UCIMessage::Quit)).await;
+            // Give the search a moment to notice, if it was going to answer it'd be before this.
+            tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(state)) = next_non_info(&w).await else { panic!("Expected Debug response"); };
+            assert_eq!(state.state, State::Quitting);
+            assert!(!state.is_searching());
+        }
+
+        #[tokio::test]
+        async fn ponderhit_turns_pondering_into_a_timed_search() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec!["e2e4".to_string(), "e7e5".to_string()]))).await;
+            w.send(Box::new(UCIMessage::Go("ponder wtime 1000 btime 1000".split(' ').map(String::from).collect()))).await;
+
+            // Pondering ignores the clock, so this is well past when the search would have stopped.
+            tokio::time::sleep(std::time::Duration::from_millis(1000)).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(state)) = next_non_info(&w).await else { panic!("Expected Debug response"); };
+            assert!(state.is_pondering());
+
+            let hit = std::time::Instant::now();
+            w.send(Box::new(UCIMessage::PonderHit)).await;
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(best_move, _))) = next_non_info(&w).await else {
+                panic!("Expected BestMove");
+            };
+            assert_ne!(best_move, "0000");
+            // The hard limit for a second on the clock is well under this.
+            assert!(hit.elapsed() < std::time::Duration::from_secs(1));
+
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(state)) = next_non_info(&w).await else { panic!("Expected Debug response"); };
+            assert!(!state.is_searching());
+        }
+
+        #[tokio::test]
+        async fn pondering_holds_a_mate_until_ponderhit() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1".to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go("ponder movetime 100".split(' ').map(String::from).collect()))).await;
+            tokio::time::sleep(std::time::Duration::from_millis(200)).await;
+
+            w.send(Box::new(UCIMessage::IsReady)).await;
+            assert_eq!(next_non_info(&w).await, Some(HazelResponse::UCIResponse(UCIMessage::ReadyOk)));
+
+            w.send(Box::new(UCIMessage::PonderHit)).await;
+            assert_eq!(next_non_info(&w).await, Some(HazelResponse::UCIResponse(UCIMessage::BestMove("a1a8".to_string(), None))));
+        }
+
+        #[tokio::test]
+        async fn stop_ends_pondering() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+            w.send(Box::new(UCIMessage::Go("ponder wtime 1000 btime 1000".split(' ').map(String::from).collect()))).await;
+            w.send(Box::new(UCIMessage::Stop)).await;
+
+            // The GUI will throw it away, but there always has to be a bestmove.
+            let Some(HazelResponse::UCIResponse(UCIMessage::BestMove(_, _))) = next_non_info(&w).await else {
+                panic!("Expected BestMove");
+            };
+
+            // Too late, there's nothing left to hit.
+            w.send(Box::new(UCIMessage::PonderHit)).await;
+            w.send(Box::new(GetState)).await;
+            let Some(HazelResponse::Debug(state)) = next_non_info(&w).await else { panic!("Expected Debug response"); };
+            assert!(!state.is_searching());
+        }
+
+        mod book {
+            use crate::book::{encode_move, polyglot_key, BookEntry};
+
+            use super::*;
+
+            // A book which only knows 1. e4, written somewhere we can load it from.
+            fn write_book(name: &str) -> std::path::PathBuf {
+                let start = Position::new(BEN::new(START_POSITION_FEN));
+                let book = Book::new(vec![BookEntry {
+                    key: polyglot_key(&start),
+                    mov: encode_move(Move::new(E2, E4, MoveType::DOUBLE_PAWN)),
+                    weight: 1,
+                    learn: 0,
+                }]);
+
+                let path = std::env::temp_dir().join(format!("hazel-{}-{}.bin", name, std::process::id()));
+                book.save(&path).unwrap();
+                path
+            }
+
+            async fn use_book(w: &WitchHandle<10, Hazel, HazelResponse>, path: &std::path::Path, own_book: bool) {
+                w.send(Box::new(UCIMessage::SetOption("Book".to_string(), Some(path.display().to_string())))).await;
+                w.send(Box::new(UCIMessage::SetOption("OwnBook".to_string(), Some(own_book.to_string())))).await;
+                w.send(Box::new(GetState)).await;
+                let Some(HazelResponse::Debug(state)) = w.read().await else { panic!("Expected Debug response"); };
+                assert!(state.book.is_some());
+            }
+
+            #[tokio::test]
+            async fn plays_from_the_book() {
+                let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+                let path = write_book("plays-from-the-book");
+                use_book(&w, &path, true).await;
+                std::fs::remove_file(&path).unwrap();
+
+                w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+                w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "3".to_string()]))).await;
+                assert_eq!(w.read().await, Some(HazelResponse::UCIResponse(UCIMessage::BestMove("e2e4".to_string(), None))));
+            }
+
+            #[tokio::test]
+            async fn searches_once_out_of_book() {
+                let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+                let path = write_book("out-of-book");
+                use_book(&w, &path, true).await;
+                std::fs::remove_file(&path).unwrap();
+
+                w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec!["d2d4".to_string()]))).await;
+                w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "1".to_string()]))).await;
+                let Some(HazelResponse::UCIResponse(UCIMessage::Info(_))) = w.read().await else { panic!("Expected Info"); };
+            }
+
+            #[tokio::test]
+            async fn ignores_the_book_without_own_book() {
+                let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+                let path = write_book("no-own-book");
+                use_book(&w, &path, false).await;
+                std::fs::remove_file(&path).unwrap();
+
+                w.send(Box::new(UCIMessage::Position(START_POSITION_FEN.to_string(), vec![]))).await;
+                w.send(Box::new(UCIMessage::Go(vec!["depth".to_string(), "1".to_string()]))).await;
+                let Some(HazelResponse::UCIResponse(UCIMessage::Info(_))) = w.read().await else { panic!("Expected Info"); };
+            }
+
+            #[tokio::test]
+            async fn missing_books_are_ignored() {
+                let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+                w.send(Box::new(UCIMessage::SetOption("Book".to_string(), Some("/no/such/book.bin".to_string())))).await;
+                w.send(Box::new(GetState)).await;
+                let Some(HazelResponse::Debug(state)) = w.read().await else { panic!("Expected Debug response"); };
+                assert!(state.book.is_none());
+                assert_eq!(state.option("Book"), Some(String::new()));
+            }
+        }
+
+        mod bitbases {
+            use crate::bitbase::Signature;
+            use crate::bitbase::tests::BITBASES;
+
+            use super::*;
+
+            async fn set_path(w: &WitchHandle<10, Hazel, HazelResponse>, path: &str) -> Hazel {
+                w.send(Box::new(UCIMessage::SetOption("BitbasePath".to_string(), Some(path.to_string())))).await;
+                w.send(Box::new(GetState)).await;
+                let Some(HazelResponse::Debug(state)) = w.read().await else { panic!("Expected Debug response"); };
+                state
+            }
+
+            #[tokio::test]
+            async fn loads_bitbases_from_a_directory() {
+                let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+                let dir = std::env::temp_dir().join(format!("hazel-bitbase-path-{}", std::process::id()));
+                BITBASES.save_dir(&dir).unwrap();
+
+                let state = set_path(&w, &dir.display().to_string()).await;
+                std::fs::remove_dir_all(&dir).unwrap();
+                assert!(state.bitbases.as_ref().unwrap().get(Signature::KRK).is_some());
+
+                // And unloads them again.
+                let state = set_path(&w, "").await;
+                assert!(state.bitbases.is_none());
+            }
+
+            #[tokio::test]
+            async fn missing_bitbases_are_ignored() {
+                let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+                let state = set_path(&w, "/no/such/bitbases").await;
+                assert!(state.bitbases.is_none());
+                assert_eq!(state.option("BitbasePath"), Some(String::new()));
+            }
+        }
+
+        #[tokio::test]
+        async fn go_without_a_position() {
+            let w : WitchHandle<10, Hazel, HazelResponse> = WitchHandle::new().await;
+
+            w.send(Box::new(UCIMessage::Go(vec![]))).await;
+            assert_eq!(w.read().await, Some(HazelResponse::UCIResponse(UCIMessage::BestMove("0000".to_string(), None))));
+        }
     }
 }
@@ -1,5 +1,9 @@
 use std::collections::HashMap;
+use std::sync::Arc;
 
+use crate::bitbase::Bitbases;
+use crate::book::Book;
+use crate::search::TranspositionTable;
 use crate::uci::UCIMessage;
 use witch::WitchHandle;
 use hazel_representation::game::chess::position::Position;
@@ -7,16 +11,18 @@ use hazel_representation::game::chess::position::Position;
 mod state;
 mod response;
 mod messages;
+mod options;
 
 pub use state::*;
 pub use response::*;
 pub use messages::*;
+pub use options::*;
 
 // NOTE: For now, I'm directly dealing with a `Position`, but I'd like to instead have Position be
 // a familiar over some Variation, which would be how the UCI stuff would get recorded, and
 // ultimately get output to PGN.
 
-#[derive(Default, PartialEq, Clone, Debug)]
+#[derive(Default, Clone, Debug)]
 pub struct Hazel {
     /// The current state of the engine.
     state: State,
@@ -25,14 +31,50 @@ pub struct Hazel {
     /// TODO: Replace this with a familiar
     /// TODO: Be able to share a cached version of this via an Arc.
     position: Option<Position>,
-    /// Options set by the UI or other external sources.
-    options: HashMap<String, Option<String>>
+    /// Options set by the UI or other external sources, by their name in `options()`. Anything
+    /// not set here has its default.
+    options: HashMap<String, Option<String>>,
+    /// Shared with each search, so it carries over between moves of a game.
+    table: Arc<TranspositionTable>,
+    /// Loaded from the `Book` option, if it's set.
+    book: Option<Arc<Book>>,
+    /// Loaded from the `BitbasePath` option, if it's set.
+    bitbases: Option<Arc<Bitbases>>,
+    /// The search started by the last `go`, until it's sent its `bestmove`.
+    search: Option<BackgroundSearch>,
+}
+
+// NOTE: The table is just a cache, so two Hazels in the same state are equal whatever's in it.
+// The same goes for a running search, it's on its way to a `bestmove` either way, and the book and
+// bitbases, which are whatever their options say.
+impl PartialEq for Hazel {
+    fn eq(&self, other: &Self) -> bool {
+        self.state == other.state && self.position == other.position && self.options == other.options
+    }
 }
 
 impl Hazel {
     pub fn is_ready(&self) -> bool {
         self.state == State::Ready
     }
+
+    /// The value of an option, whether the GUI set it or not. Option names are case-insensitive.
+    pub fn option(&self, name: &str) -> Option<String> {
+        let options = options();
+        let option = options.get(name)?;
+        match self.options.get(option.name()) {
+            Some(value) => value.clone(),
+            None => option.default_value(),
+        }
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.search.is_some()
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.search.as_ref().is_some_and(|search| search.is_pondering())
+    }
 }
 
 pub type WitchHazel<const BUF_SIZE: usize> = WitchHandle<BUF_SIZE, Hazel, HazelResponse>;
@@ -0,0 +1,24 @@
+use std::sync::LazyLock;
+
+use crate::search::{DEFAULT_HASH_MB, MAX_HASH_MB, MAX_MULTI_PV};
+use crate::uci::{OptionRegistry, UCIOption};
+
+/// Everything the GUI can set, in the order it's advertised on `uci`.
+pub fn options() -> &'static OptionRegistry {
+    &OPTIONS
+}
+
+static OPTIONS: LazyLock<OptionRegistry> = LazyLock::new(|| {
+    OptionRegistry::new()
+        .with(UCIOption::spin("Hash", DEFAULT_HASH_MB as i64, 1, MAX_HASH_MB as i64))
+        .with(UCIOption::button("Clear Hash"))
+        // NOTE: We don't do anything different when pondering is allowed, this is just so the GUI
+        // knows it can send `go ponder`.
+        .with(UCIOption::check("Ponder", false))
+        .with(UCIOption::spin("MultiPV", 1, 1, MAX_MULTI_PV as i64))
+        // Play straight from `Book` (a Polyglot .bin) while it has anything to say.
+        .with(UCIOption::check("OwnBook", false))
+        .with(UCIOption::string("Book", ""))
+        // A directory of `<SIGNATURE>.bitbase` files, see `Bitbases::save_dir`.
+        .with(UCIOption::string("BitbasePath", ""))
+});
@@ -1,3 +1,4 @@
+use crate::evaluation::Evaluation;
 use crate::uci::UCIMessage;
 use hazel_representation::game::position::Position;
 
@@ -8,5 +9,6 @@ pub enum HazelResponse {
     #[default] Silence,
     UCIResponse(UCIMessage),
     Debug(Hazel),
-    Position(Option<Position>)
+    Position(Option<Position>),
+    Evaluation(Option<Evaluation>),
 }
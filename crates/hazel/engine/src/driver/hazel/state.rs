@@ -1,3 +1,9 @@
+use std::sync::{Arc, OnceLock};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::uci::UCIMessage;
+
 #[derive(Clone, Copy, PartialEq, Debug, Default)]
 pub enum State {
     #[default] Idle,
@@ -5,3 +11,62 @@ pub enum State {
     Pondering,
     Quitting,
 }
+
+/// A search running on its own thread. The actor keeps one of these so it can stop the search
+/// while it carries on with other messages.
+#[derive(Clone, Debug)]
+pub struct BackgroundSearch {
+    /// Shared with the search, which gives up as soon as it sees this set.
+    pub stop: Arc<AtomicBool>,
+    /// `go infinite` mustn't send `bestmove` until the GUI says `stop`, even if the search has
+    /// nothing left to do (e.g., it found a mate).
+    pub infinite: bool,
+    /// For `go ponder`, shared with the search's `TimeManager`, and set on `ponderhit`.
+    pub ponderhit: Option<Arc<OnceLock<Instant>>>,
+    /// The hard time limit, if it couldn't be started yet because we're pondering.
+    pub hard_limit: Option<Duration>,
+    /// The `bestmove` of a search which finished on its own while the GUI still expects it to be
+    /// running, waiting for `stop` (or `ponderhit`).
+    pub held: Option<Box<UCIMessage>>,
+}
+
+impl BackgroundSearch {
+    pub fn new(stop: Arc<AtomicBool>, infinite: bool) -> Self {
+        Self { stop, infinite, ponderhit: None, hard_limit: None, held: None }
+    }
+
+    pub fn pondering(mut self, ponderhit: Arc<OnceLock<Instant>>, hard_limit: Option<Duration>) -> Self {
+        self.ponderhit = Some(ponderhit);
+        self.hard_limit = hard_limit;
+        self
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponderhit.as_ref().is_some_and(|hit| hit.get().is_none())
+    }
+
+    /// The opponent played the move we were pondering on, so from now on this is a normal search.
+    pub fn ponderhit(&self) {
+        if let Some(hit) = &self.ponderhit {
+            _ = hit.set(Instant::now());
+        }
+    }
+
+    /// Whether the GUI still thinks the search is running, so its `bestmove` has to wait.
+    pub fn must_wait(&self) -> bool {
+        !self.is_stopped() && (self.infinite || self.is_pondering())
+    }
+
+    pub fn stop(&self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Whether `stop` belongs to this search, rather than an earlier one.
+    pub fn owns(&self, stop: &Arc<AtomicBool>) -> bool {
+        Arc::ptr_eq(&self.stop, stop)
+    }
+}
@@ -0,0 +1,195 @@
+use std::fmt::Display;
+
+use hazel_core::color::{Color, COLOR_COUNT};
+use hazel_core::interface::Query;
+use hazel_core::square::Square;
+use hazel_representation::game::position::Position;
+
+use crate::bitbase::{Bitbases, Wdl};
+use crate::search::Score;
+
+mod tables;
+
+pub use tables::{ENDGAME_VALUES, MAX_PHASE, MIDGAME_VALUES};
+use tables::{ENDGAME_TABLES, MIDGAME_TABLES, PHASE_WEIGHTS};
+
+/// A small bonus for having the move.
+pub const TEMPO: Score = 10;
+
+/// What a position the bitbases say is won is worth, before the usual evaluation is added. Well
+/// clear of anything the evaluation can come up with, but still short of a mate.
+pub const KNOWN_WIN: Score = 20_000;
+
+/// A static evaluation of the position, from the side to move's point of view.
+pub fn evaluate(position: &Position) -> Score {
+    Evaluation::new(position).score()
+}
+
+/// Same as `evaluate`, but positions the bitbases have are scored as they say. The usual
+/// evaluation is still added to wins and losses, so the search has some idea of how to make
+/// progress towards mate.
+pub fn evaluate_with_bitbases(position: &Position, bitbases: &Bitbases) -> Score {
+    match bitbases.probe(position) {
+        Some(Wdl::Win) => KNOWN_WIN + evaluate(position),
+        Some(Wdl::Loss) => -KNOWN_WIN + evaluate(position),
+        Some(Wdl::Draw) => 0,
+        None => evaluate(position),
+    }
+}
+
+/// The pieces of an evaluation, kept separate so they can be inspected (e.g., by tests, or the
+/// UI). Everything but `score` is from each color's own point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Evaluation {
+    /// Material and piece-square bonuses for each color, as if it were the middlegame.
+    pub midgame: [Score; COLOR_COUNT],
+    /// Same again, but for the endgame.
+    pub endgame: [Score; COLOR_COUNT],
+    /// From `MAX_PHASE` with all the pieces on the board, down to 0 with none of them.
+    pub phase: Score,
+    pub side_to_move: Color,
+}
+
+impl Evaluation {
+    pub fn new(position: &Position) -> Self {
+        let mut eval = Evaluation {
+            midgame: [0; COLOR_COUNT],
+            endgame: [0; COLOR_COUNT],
+            phase: 0,
+            side_to_move: position.hero(),
+        };
+
+        for sq in position.all_blockers() {
+            let occupant = position.get(sq);
+            let (Some(piece), Some(color)) = (occupant.piece(), occupant.color()) else { continue; };
+
+            let idx = table_index(sq, color);
+            let side = color as usize;
+            let piece = piece as usize;
+
+            eval.midgame[side] += MIDGAME_VALUES[piece] + MIDGAME_TABLES[piece][idx];
+            eval.endgame[side] += ENDGAME_VALUES[piece] + ENDGAME_TABLES[piece][idx];
+            eval.phase += PHASE_WEIGHTS[piece];
+        }
+
+        // Early promotions can push this over.
+        eval.phase = eval.phase.min(MAX_PHASE);
+        eval
+    }
+
+    /// The middlegame and endgame scores blended by phase, from white's point of view, without
+    /// the tempo bonus.
+    pub fn tapered(&self) -> Score {
+        let white = Color::WHITE as usize;
+        let black = Color::BLACK as usize;
+
+        let midgame = self.midgame[white] - self.midgame[black];
+        let endgame = self.endgame[white] - self.endgame[black];
+
+        (midgame * self.phase + endgame * (MAX_PHASE - self.phase)) / MAX_PHASE
+    }
+
+    /// The final score, from the side to move's point of view.
+    pub fn score(&self) -> Score {
+        let tapered = if self.side_to_move == Color::WHITE { self.tapered() } else { -self.tapered() };
+        tapered + TEMPO
+    }
+}
+
+impl Display for Evaluation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let white = Color::WHITE as usize;
+        let black = Color::BLACK as usize;
+
+        writeln!(f, "         | White | Black")?;
+        writeln!(f, "Midgame  | {:>5} | {:>5}", self.midgame[white], self.midgame[black])?;
+        writeln!(f, "Endgame  | {:>5} | {:>5}", self.endgame[white], self.endgame[black])?;
+        writeln!(f, "Phase: {}/{}", self.phase, MAX_PHASE)?;
+        writeln!(f, "Tapered (white): {}", self.tapered())?;
+        write!(f, "Score ({:?} to move): {}", self.side_to_move, self.score())
+    }
+}
+
+// The tables are laid out a8 first, see `tables`.
+fn table_index(sq: Square, color: Color) -> usize {
+    match color {
+        Color::WHITE => sq.index() ^ 56,
+        Color::BLACK => sq.index(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::piece::Piece;
+
+    use super::*;
+    use crate::bitbase::tests::BITBASES;
+
+    fn eval(fen: &str) -> Evaluation {
+        Evaluation::new(&Position::new(BEN::new(fen)))
+    }
+
+    #[test]
+    fn start_position_is_level_but_for_the_tempo() {
+        let eval = Evaluation::new(&Position::new(BEN::start_position()));
+
+        assert_eq!(eval.phase, MAX_PHASE);
+        assert_eq!(eval.tapered(), 0);
+        assert_eq!(eval.score(), TEMPO);
+    }
+
+    #[test]
+    fn evaluation_is_from_the_side_to_move() {
+        let white = eval("4k3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        let black = eval("4k3/8/8/8/8/8/8/3QK3 b - - 0 1");
+
+        assert!(white.tapered() > 0);
+        assert_eq!(white.score(), white.tapered() + TEMPO);
+        assert_eq!(black.score(), -black.tapered() + TEMPO);
+    }
+
+    #[test]
+    fn mirrored_positions_are_the_same_for_the_side_to_move() {
+        let white = eval("r1bqkbnr/pppp1ppp/2n5/4p3/4P3/5N2/PPPP1PPP/RNBQKB1R w KQkq - 2 3");
+        let black = eval("rnbqkb1r/pppp1ppp/5n2/4p3/4P3/2N5/PPPP1PPP/R1BQKBNR b KQkq - 2 3");
+
+        assert_eq!(white.score(), black.score());
+    }
+
+    #[test]
+    fn bitbases_know_better() {
+        let score = |fen: &str| evaluate_with_bitbases(&Position::new(BEN::new(fen)), &BITBASES);
+
+        assert!(score("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1") > KNOWN_WIN);
+        assert!(score("4k3/8/4K3/4P3/8/8/8/8 b - - 0 1") < -KNOWN_WIN);
+        // A pawn up, but the rook pawn can't be made to count.
+        assert_eq!(score("k7/8/K7/P7/8/8/8/8 w - - 0 1"), 0);
+        // Nothing to look up.
+        let fen = "4k3/8/8/8/8/8/4P3/4K2R w - - 0 1";
+        assert_eq!(score(fen), evaluate(&Position::new(BEN::new(fen))));
+    }
+
+    #[test]
+    fn phase_tapers_towards_the_endgame() {
+        // Just kings and pawns, so it's all endgame.
+        let eval = self::eval("4k3/pppp4/8/8/8/8/PPPP4/4K3 w - - 0 1");
+        assert_eq!(eval.phase, 0);
+        assert_eq!(eval.tapered(), eval.endgame[Color::WHITE as usize] - eval.endgame[Color::BLACK as usize]);
+    }
+
+    #[test]
+    fn material_dominates() {
+        // Up a rook, wherever it's standing.
+        let eval = self::eval("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert!(eval.score() > ENDGAME_VALUES[Piece::Rook as usize] - 100);
+    }
+
+    #[test]
+    fn advanced_pawns_are_worth_more() {
+        let home = eval("4k3/8/8/8/8/8/4P3/4K3 w - - 0 1");
+        let advanced = eval("4k3/8/4P3/8/8/8/8/4K3 w - - 0 1");
+
+        assert!(advanced.score() > home.score());
+    }
+}
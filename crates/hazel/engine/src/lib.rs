@@ -1,6 +1,10 @@
 #![feature(assert_matches)]
 pub mod uci;
+pub mod bitbase;
+pub mod book;
 pub mod driver;
+pub mod evaluation;
+pub mod search;
 
 // Spec that Engine adapters must implement to be included in the Hazel UI.
 //
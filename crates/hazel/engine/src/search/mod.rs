@@ -0,0 +1,612 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use hazel_generator::{MoveGenerator, MoveOrdering, MovePicker};
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+use crate::bitbase::{Bitbases, Wdl};
+use crate::evaluation::{evaluate, evaluate_with_bitbases};
+use crate::uci::{UCIInfo, UCIMessage, UCIScore};
+
+mod quiescence;
+mod time;
+mod transposition;
+
+pub use quiescence::DELTA_MARGIN;
+pub use time::{TimeManager, DEFAULT_MOVES_TO_GO, MOVE_OVERHEAD};
+pub use transposition::{Bound, Entry, TranspositionTable, DEFAULT_HASH_MB, MAX_HASH_MB};
+
+/// Centipawns, from the point of view of whoever is to move.
+pub type Score = i32;
+
+/// Bigger than any score the search can produce.
+pub const INFINITY: Score = 32_000;
+/// The score for delivering mate right now. Mates further off are worth one less per ply, so the
+/// search prefers the quickest mate (and the slowest way to get mated).
+pub const MATE: Score = 30_000;
+/// No search is going to get anywhere near this deep, it only bounds which scores are mates.
+pub const MAX_PLY: usize = 256;
+/// The most lines `MultiPV` can ask for.
+pub const MAX_MULTI_PV: usize = 64;
+
+/// Whether a score is a forced mate for either side.
+pub fn is_mate(score: Score) -> bool {
+    score.abs() >= MATE - MAX_PLY as Score
+}
+
+/// What a completed iteration of the search found.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SearchResult {
+    /// The principal variation, starting with the best move. Empty if there are no legal moves.
+    pub pv: Vec<Move>,
+    pub score: Score,
+    pub depth: usize,
+    pub nodes: usize,
+    /// Which line this is, best first from 1, when searching for more than one. `None` otherwise.
+    pub multipv: Option<usize>,
+}
+
+impl SearchResult {
+    pub fn best_move(&self) -> Option<Move> {
+        self.pv.first().copied()
+    }
+
+    pub fn ponder_move(&self) -> Option<Move> {
+        self.pv.get(1).copied()
+    }
+
+    /// Moves to mate, UCI style. Positive if we're mating, negative if we're getting mated.
+    pub fn mate_in(&self) -> Option<i32> {
+        if !is_mate(self.score) { return None; }
+
+        if self.score > 0 {
+            Some((MATE - self.score + 1) / 2)
+        } else {
+            Some(-(MATE + self.score) / 2)
+        }
+    }
+
+    /// The `info` line for this iteration.
+    pub fn info(&self) -> UCIMessage {
+        let score = match self.mate_in() {
+            Some(moves) => UCIScore::Mate(moves),
+            None => UCIScore::Centipawns(self.score),
+        };
+
+        UCIMessage::Info(UCIInfo {
+            depth: Some(self.depth),
+            multipv: self.multipv,
+            score: Some(score),
+            nodes: Some(self.nodes),
+            pv: self.pv.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Iterative deepening negamax with alpha-beta pruning.
+///
+/// The search goes until it reaches the depth it's told, or the time manager says it's time to
+/// stop, or until someone sets the stop flag. Only completed iterations count, so a search which
+/// is stopped part way through the next one still has the last one's result.
+#[derive(Debug)]
+pub struct Search {
+    generator: MoveGenerator,
+    table: Arc<TranspositionTable>,
+    ordering: MoveOrdering,
+    nodes: usize,
+    // The PV from the last iteration, tried first on the next one.
+    previous_pv: Vec<Move>,
+    // The moves made from the root to get to the current node.
+    line: Vec<Move>,
+    // If not empty, only these are searched at the root.
+    root_moves: Vec<Move>,
+    // How many lines to find, and the root moves of the ones already found this iteration.
+    multi_pv: usize,
+    excluded: Vec<Move>,
+    time: TimeManager,
+    node_limit: Option<usize>,
+    // Looked up at every node, if we have any.
+    bitbases: Option<Arc<Bitbases>>,
+    // Set from outside to stop the search.
+    stop: Arc<AtomicBool>,
+    // Set once we've noticed, so the rest of the search unwinds quickly.
+    stopped: bool,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Search {
+    pub fn new() -> Self {
+        Self::with_table(Arc::new(TranspositionTable::default()))
+    }
+
+    /// Search with a table shared with someone else, so it persists from one search to the next.
+    pub fn with_table(table: Arc<TranspositionTable>) -> Self {
+        Self {
+            generator: MoveGenerator::new(),
+            table,
+            ordering: MoveOrdering::new(),
+            nodes: 0,
+            previous_pv: vec![],
+            line: vec![],
+            root_moves: vec![],
+            multi_pv: 1,
+            excluded: vec![],
+            time: TimeManager::unlimited(),
+            node_limit: None,
+            bitbases: None,
+            stop: Arc::new(AtomicBool::new(false)),
+            stopped: false,
+        }
+    }
+
+    pub fn with_time(mut self, time: TimeManager) -> Self {
+        self.time = time;
+        self
+    }
+
+    pub fn with_node_limit(mut self, nodes: Option<usize>) -> Self {
+        self.node_limit = nodes;
+        self
+    }
+
+    pub fn with_bitbases(mut self, bitbases: Option<Arc<Bitbases>>) -> Self {
+        self.bitbases = bitbases;
+        self
+    }
+
+    /// Share a stop flag with whoever is running the search. Setting it stops the search as soon
+    /// as it's noticed.
+    pub fn with_stop(mut self, stop: Arc<AtomicBool>) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Only search these moves at the root (e.g., for `go searchmoves`). They must be legal, and
+    /// exactly as the generator would produce them. Empty means everything.
+    pub fn with_root_moves(mut self, moves: Vec<Move>) -> Self {
+        self.root_moves = moves;
+        self
+    }
+
+    /// Find the best `lines` moves at the root, each with its own PV, rather than just the best
+    /// one. Costs about one search per line.
+    pub fn with_multi_pv(mut self, lines: usize) -> Self {
+        self.multi_pv = lines.clamp(1, MAX_MULTI_PV);
+        self
+    }
+
+    pub fn table(&self) -> &Arc<TranspositionTable> {
+        &self.table
+    }
+
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    /// Searches to each depth from 1 up to `max_depth`, calling `report` after each one, and
+    /// returns the deepest completed result. Stops early once it finds a forced mate, since going
+    /// deeper won't find a shorter one.
+    ///
+    /// With `with_multi_pv`, `report` is called once for each line, best first, and the best line
+    /// is returned.
+    ///
+    /// Even if stopped straight away, a move is returned if there is one.
+    ///
+    /// NOTE: The position is made and unmade on, but is left as it was found.
+    pub fn run(&mut self, position: &mut Position, max_depth: usize, mut report: impl FnMut(&SearchResult)) -> SearchResult {
+        self.nodes = 0;
+        self.stopped = false;
+        self.line.clear();
+        self.table.new_search();
+        self.ordering.new_search();
+
+        let mut result = SearchResult::default();
+        let mut previous_lines: Vec<Vec<Move>> = vec![];
+
+        'deepening: for depth in 1..=max_depth.max(1) {
+            let mut lines = vec![];
+            self.excluded.clear();
+
+            for idx in 0..self.multi_pv {
+                self.previous_pv = previous_lines.get(idx).cloned().unwrap_or_default();
+
+                let mut pv = vec![];
+                let score = self.negamax(position, depth, 0, -INFINITY, INFINITY, &mut pv, true);
+
+                if self.stopped { break 'deepening; }
+                // Out of moves, there are fewer lines than were asked for.
+                if pv.is_empty() && idx > 0 { break; }
+
+                let line = SearchResult { pv, score, depth, nodes: self.nodes, multipv: (self.multi_pv > 1).then_some(idx + 1) };
+                report(&line);
+
+                if idx == 0 { result = line.clone(); }
+                let Some(&best) = line.pv.first() else { break; };
+                self.excluded.push(best);
+                lines.push(line.pv);
+            }
+
+            if result.pv.is_empty() || is_mate(result.score) { break; }
+            if !self.time.keep_going(&result) { break; }
+            previous_lines = lines;
+        }
+
+        self.excluded.clear();
+
+        // Stopped before the first iteration finished, anything legal is better than nothing.
+        if result.pv.is_empty() {
+            if let Some(mov) = self.fallback_move(position) {
+                result = SearchResult { pv: vec![mov], nodes: self.nodes, ..Default::default() };
+            }
+        }
+
+        result
+    }
+
+    fn fallback_move(&self, position: &Position) -> Option<Move> {
+        if !self.stopped { return None; }
+
+        self.root_moves.first().copied().or_else(|| self.generator.generate_moves(position).first().copied())
+    }
+
+    /// Whether to give up on the search. Once it's true, it stays true until the next `run`.
+    fn should_stop(&mut self) -> bool {
+        if !self.stopped {
+            self.stopped = self.stop.load(Ordering::Relaxed) || self.node_limit.is_some_and(|limit| self.nodes >= limit);
+        }
+        self.stopped
+    }
+
+    /// The static evaluation, using the bitbases if we have them.
+    fn evaluate(&self, position: &Position) -> Score {
+        match &self.bitbases {
+            Some(bitbases) => evaluate_with_bitbases(position, bitbases),
+            None => evaluate(position),
+        }
+    }
+
+    /// Fail-hard negamax. `pv` is filled with the best line found from here, if any move beat
+    /// `alpha`. `on_pv` is set while we're following the previous iteration's PV, so we know to
+    /// try its move first, otherwise the table's best move goes first.
+    #[allow(clippy::too_many_arguments)]
+    fn negamax(&mut self, position: &mut Position, depth: usize, ply: usize, mut alpha: Score, beta: Score, pv: &mut Vec<Move>, on_pv: bool) -> Score {
+        pv.clear();
+
+        if self.should_stop() { return 0; }
+
+        // Coming back to a position is as good as a draw, whoever could repeat it once can keep
+        // doing it. Not at the root though, we still need a move.
+        //
+        // NOTE: This misses a mate delivered on the fiftieth move, which is rare enough not to
+        // worry about.
+        if ply > 0 && (position.is_repetition() || position.is_fifty_move_draw()) {
+            return 0;
+        }
+
+        // Nothing to be gained searching a draw. Wins are still searched though, so we find the
+        // mate.
+        if ply > 0 && self.bitbases.as_ref().and_then(|bitbases| bitbases.probe(position)) == Some(Wdl::Draw) {
+            return 0;
+        }
+
+        if depth == 0 {
+            return self.quiesce(position, ply, alpha, beta);
+        }
+
+        self.nodes += 1;
+
+        let key = position.zobrist().position;
+        let entry = self.table.probe(key);
+
+        // Never cut at the root, we need a move to play.
+        if let Some(entry) = entry.filter(|entry| ply > 0 && entry.depth as usize >= depth) {
+            let score = entry.score(ply);
+            let cutoff = match entry.bound {
+                Bound::Exact => Some(score.clamp(alpha, beta)),
+                Bound::Lower if score >= beta => Some(beta),
+                Bound::Upper if score <= alpha => Some(alpha),
+                _ => None,
+            };
+
+            if let Some(score) = cutoff {
+                // Only an exact score inside the window extends the PV.
+                if let (Some(mov), true) = (entry.best_move, score > alpha && score < beta) {
+                    pv.push(mov);
+                }
+                return score;
+            }
+        }
+
+        let pv_move = if on_pv { self.previous_pv.get(ply).copied() } else { None };
+        let hash_move = pv_move.or(entry.and_then(|entry| entry.best_move));
+        let previous = self.line.last().copied();
+
+        let mut picker = MovePicker::new()
+            .with_hash_move(hash_move)
+            .with_killers(&self.ordering.quiet_candidates(ply, previous));
+        let mut line = vec![];
+        let mut searched = 0;
+        let mut quiets_tried = vec![];
+        let original_alpha = alpha;
+
+        while let Some(mov) = picker.next_ordered_move(&self.generator, position, &self.ordering) {
+            if ply == 0 && !self.is_root_move(mov) { continue; }
+            searched += 1;
+
+            position.make(mov);
+            self.line.push(mov);
+            let score = -self.negamax(position, depth - 1, ply + 1, -beta, -alpha, &mut line, on_pv && Some(mov) == pv_move);
+            self.line.pop();
+            position.unmake();
+
+            // Whatever came back is meaningless, and so is anything we'd work out from it.
+            if self.stopped { return 0; }
+
+            let quiet = !mov.is_capture() && !mov.is_promotion();
+
+            if score >= beta {
+                if quiet { self.refuted_by(mov, &quiets_tried, depth, ply, previous); }
+                self.table.store(key, depth, ply, Bound::Lower, beta, Some(mov));
+                return beta;
+            }
+
+            if quiet { quiets_tried.push(mov); }
+
+            if score > alpha {
+                alpha = score;
+                pv.clear();
+                pv.push(mov);
+                pv.extend_from_slice(&line);
+            }
+        }
+
+        if searched == 0 {
+            return if position.checkers().is_nonempty() { -MATE + ply as Score } else { 0 };
+        }
+
+        // With some of the root moves left out, this isn't the root's real score.
+        if ply > 0 || self.excluded.is_empty() {
+            if alpha > original_alpha {
+                self.table.store(key, depth, ply, Bound::Exact, alpha, pv.first().copied());
+            } else {
+                self.table.store(key, depth, ply, Bound::Upper, alpha, None);
+            }
+        }
+
+        alpha
+    }
+
+    /// Whether `mov` is one we're meant to search at the root, given `searchmoves` and the lines
+    /// MultiPV has already found.
+    fn is_root_move(&self, mov: Move) -> bool {
+        (self.root_moves.is_empty() || self.root_moves.contains(&mov)) && !self.excluded.contains(&mov)
+    }
+
+    /// A quiet move caused a cutoff, so remember it, and that the quiets before it didn't.
+    fn refuted_by(&mut self, mov: Move, quiets_tried: &[Move], depth: usize, ply: usize, previous: Option<Move>) {
+        let bonus = (depth * depth) as Score;
+
+        self.ordering.store_killer(ply, mov);
+        self.ordering.update_history(mov, bonus);
+        for tried in quiets_tried {
+            self.ordering.update_history(*tried, -bonus);
+        }
+        if let Some(previous) = previous {
+            self.ordering.store_countermove(previous, mov);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+    use crate::bitbase::tests::BITBASES;
+    use crate::evaluation::KNOWN_WIN;
+
+    fn search(fen: &str, depth: usize) -> SearchResult {
+        let mut position = Position::new(BEN::new(fen));
+        Search::new().run(&mut position, depth, |_| {})
+    }
+
+    #[test]
+    fn finds_a_back_rank_mate() {
+        let result = search("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1", 3);
+
+        assert_eq!(result.best_move(), Some(Move::new(A1, A8, MoveType::QUIET)));
+        assert_eq!(result.score, MATE - 1);
+        assert_eq!(result.mate_in(), Some(1));
+        // Found at depth 1, so there's no reason to go on.
+        assert_eq!(result.depth, 1);
+    }
+
+    #[test]
+    fn takes_a_hanging_queen() {
+        let result = search("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1", 2);
+        assert_eq!(result.best_move(), Some(Move::new(D2, D5, MoveType::CAPTURE)));
+    }
+
+    #[test]
+    fn does_not_grab_a_defended_pawn_at_the_horizon() {
+        let result = search("4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1", 1);
+        assert_ne!(result.best_move(), Some(Move::new(D1, D5, MoveType::CAPTURE)));
+        assert!(result.score > 0);
+    }
+
+    #[test]
+    fn cutoffs_are_remembered() {
+        let mut search = Search::new();
+        let mut position = Position::new(BEN::start_position());
+        search.run(&mut position, 3, |_| {});
+
+        // Somewhere, some quiet move refuted something.
+        assert!((0..3).any(|ply| !search.ordering.killers(ply).is_empty()));
+        assert!(search.line.is_empty());
+    }
+
+    #[test]
+    fn stops_when_told_to() {
+        let mut search = Search::new();
+        search.stop_flag().store(true, Ordering::Relaxed);
+
+        let mut position = Position::new(BEN::start_position());
+        let result = search.run(&mut position, 10, |_| panic!("No iteration should have finished"));
+
+        // Still have to play something.
+        assert!(MoveGenerator::new().generate_moves(&position).contains(&result.best_move().unwrap()));
+    }
+
+    #[test]
+    fn node_limit() {
+        let mut search = Search::new().with_node_limit(Some(2000));
+        let mut position = Position::new(BEN::start_position());
+        let result = search.run(&mut position, 64, |_| {});
+
+        assert!(result.depth < 64);
+        assert!(result.best_move().is_some());
+        // It's checked often enough that it doesn't get far past the limit.
+        assert!(search.nodes() < 2100);
+    }
+
+    #[test]
+    fn stops_once_the_soft_limit_is_up() {
+        let time = TimeManager::with_limits(Some(std::time::Duration::ZERO), None);
+        let mut search = Search::new().with_time(time);
+        let mut position = Position::new(BEN::start_position());
+
+        assert_eq!(search.run(&mut position, 64, |_| {}).depth, 1);
+    }
+
+    #[test]
+    fn finds_mate_for_black_too() {
+        let result = search("r5k1/5ppp/8/8/8/8/5PPP/6K1 b - - 0 1", 2);
+        assert_eq!(result.best_move(), Some(Move::new(A8, A1, MoveType::QUIET)));
+        assert_eq!(result.mate_in(), Some(1));
+    }
+
+    #[test]
+    fn mated_root_has_no_best_move() {
+        // White to move, already mated.
+        let result = search("6k1/5ppp/8/8/8/8/5PPP/r5K1 w - - 0 1", 3);
+        assert_eq!(result.best_move(), None);
+        assert_eq!(result.score, -MATE);
+    }
+
+    #[test]
+    fn stalemate_is_a_draw() {
+        let result = search("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1", 2);
+        assert_eq!(result.best_move(), None);
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn repeating_saves_a_lost_position() {
+        // A queen down, but Ke8 takes us back to where we started.
+        let mut position = Position::with_moves(BEN::new("4k3/8/8/8/8/8/8/Q3K3 w - - 0 1"), vec![
+            Move::new(E1, D1, MoveType::QUIET), Move::new(E8, D8, MoveType::QUIET),
+            Move::new(D1, E1, MoveType::QUIET),
+        ]);
+        let result = Search::new().run(&mut position, 2, |_| {});
+        assert_eq!(result.best_move(), Some(Move::new(D8, E8, MoveType::QUIET)));
+        assert_eq!(result.score, 0);
+    }
+
+    #[test]
+    fn fifty_moves_is_a_draw() {
+        assert_eq!(search("4k3/8/8/8/8/8/8/Q3K3 b - - 99 80", 2).score, 0);
+        assert!(search("4k3/8/8/8/8/8/8/Q3K3 b - - 0 80", 2).score < -500);
+    }
+
+    #[test]
+    fn bitbases_settle_the_endgame() {
+        let bitbases = Some(Arc::new(BITBASES.clone()));
+        let with_bitbases = |fen: &str| {
+            let mut position = Position::new(BEN::new(fen));
+            Search::new().with_bitbases(bitbases.clone()).run(&mut position, 2, |_| {})
+        };
+
+        assert!(with_bitbases("4k3/8/4K3/4P3/8/8/8/8 w - - 0 1").score > KNOWN_WIN);
+        // Without them, the pawn looks like it's worth something.
+        assert_eq!(with_bitbases("k7/8/K7/P7/8/8/8/8 w - - 0 1").score, 0);
+        assert!(search("k7/8/K7/P7/8/8/8/8 w - - 0 1", 2).score > 0);
+    }
+
+    #[test]
+    fn pv_is_a_legal_line() {
+        let mut position = Position::new(BEN::start_position());
+        let mut reported = vec![];
+        let result = Search::new().run(&mut position, 3, |result| reported.push(result.depth));
+
+        assert_eq!(reported, vec![1, 2, 3]);
+        assert_eq!(result.pv.len(), 3);
+
+        let gen = MoveGenerator::new();
+        for mov in result.pv {
+            assert!(gen.generate_moves(&position).contains(&mov));
+            position.make(mov);
+        }
+    }
+
+    #[test]
+    fn info_line() {
+        let result = SearchResult {
+            pv: vec![Move::new(E2, E4, MoveType::DOUBLE_PAWN), Move::new(E7, E5, MoveType::DOUBLE_PAWN)],
+            score: 25,
+            depth: 2,
+            nodes: 100,
+            multipv: None,
+        };
+        assert_eq!(format!("{}", result.info()), "info depth 2 score cp 25 nodes 100 pv e2e4 e7e5");
+
+        let result = SearchResult { multipv: Some(3), ..result };
+        assert_eq!(format!("{}", result.info()), "info depth 2 multipv 3 score cp 25 nodes 100 pv e2e4 e7e5");
+
+        let result = SearchResult { pv: vec![], score: -MATE + 2, depth: 2, nodes: 10, multipv: None };
+        assert_eq!(format!("{}", result.info()), "info depth 2 score mate -1 nodes 10");
+    }
+
+    #[test]
+    fn multi_pv_reports_ranked_lines() {
+        // Taking the queen is best, by a long way.
+        let mut position = Position::new(BEN::new("4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1"));
+        let mut lines = vec![];
+        let result = Search::new().with_multi_pv(3).run(&mut position, 2, |line| lines.push(line.clone()));
+
+        let last: Vec<&SearchResult> = lines.iter().filter(|line| line.depth == 2).collect();
+        assert_eq!(last.iter().map(|line| line.multipv).collect::<Vec<_>>(), vec![Some(1), Some(2), Some(3)]);
+        assert_eq!(last[0].best_move(), Some(Move::new(D2, D5, MoveType::CAPTURE)));
+        assert_eq!(result, *last[0]);
+
+        // Every line starts with a different move, and they get worse.
+        assert_ne!(last[1].best_move(), last[0].best_move());
+        assert_ne!(last[2].best_move(), last[1].best_move());
+        assert_ne!(last[2].best_move(), last[0].best_move());
+        assert!(last[0].score >= last[1].score && last[1].score >= last[2].score);
+    }
+
+    #[test]
+    fn multi_pv_stops_at_the_number_of_legal_moves() {
+        // Just Kh2, a3 and a4.
+        let mut position = Position::new(BEN::new("k5r1/8/8/8/8/8/P7/7K w - - 0 1"));
+        let legal = MoveGenerator::new().generate_moves(&position).len();
+        assert_eq!(legal, 3);
+
+        let mut lines = vec![];
+        Search::new().with_multi_pv(10).run(&mut position, 1, |line| lines.push(line.clone()));
+        assert_eq!(lines.len(), legal);
+    }
+}
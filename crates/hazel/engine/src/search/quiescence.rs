@@ -0,0 +1,123 @@
+use hazel_core::interface::Query;
+use hazel_core::piece::Piece;
+use hazel_generator::{mvv_lva, see_capture, GenerationMode, SEE_VALUES};
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+use super::{Score, Search, MATE, MAX_PLY};
+
+/// Slack for delta pruning, a capture which can't get within this of alpha isn't worth looking at.
+pub const DELTA_MARGIN: Score = 200;
+
+impl Search {
+    /// Extends a leaf through captures and promotions until the position is quiet, so the static
+    /// evaluation is never taken in the middle of an exchange.
+    ///
+    /// The side to move can always "stand pat" and take the static evaluation instead of capturing,
+    /// except when in check, where every evasion is searched so mates aren't missed.
+    pub(super) fn quiesce(&mut self, position: &mut Position, ply: usize, mut alpha: Score, beta: Score) -> Score {
+        if self.should_stop() { return 0; }
+        self.nodes += 1;
+
+        let in_check = position.checkers().is_nonempty();
+
+        if ply >= MAX_PLY { return self.evaluate(position); }
+
+        if in_check {
+            let evasions = self.generator.generate_moves(position);
+            if evasions.is_empty() { return -MATE + ply as Score; }
+
+            for mov in evasions {
+                position.make(mov);
+                let score = -self.quiesce(position, ply + 1, -beta, -alpha);
+                position.unmake();
+
+                if self.stopped { return 0; }
+
+                if score >= beta { return beta; }
+                alpha = alpha.max(score);
+            }
+
+            return alpha;
+        }
+
+        let stand_pat = self.evaluate(position);
+        if stand_pat >= beta { return beta; }
+
+        // Not even winning a queen (and promoting) would get us back to alpha.
+        let best_case = SEE_VALUES[Piece::Queen as usize] * 2 - SEE_VALUES[Piece::Pawn as usize];
+        if stand_pat + best_case < alpha { return alpha; }
+
+        alpha = alpha.max(stand_pat);
+
+        let mut captures = self.generator.generate(position, GenerationMode::Tactical);
+        captures.sort_by_key(|mov| -mvv_lva(position, mov));
+
+        for mov in captures {
+            if !mov.is_promotion() {
+                // Delta pruning, this capture can't raise alpha even if nothing is taken back.
+                if stand_pat + victim_value(position, &mov) + DELTA_MARGIN < alpha { continue; }
+                // It loses material outright.
+                if see_capture(position, &mov) < 0 { continue; }
+            }
+
+            position.make(mov);
+            let score = -self.quiesce(position, ply + 1, -beta, -alpha);
+            position.unmake();
+
+            if self.stopped { return 0; }
+
+            if score >= beta { return beta; }
+            alpha = alpha.max(score);
+        }
+
+        alpha
+    }
+}
+
+fn victim_value(position: &Position, mov: &Move) -> Score {
+    if mov.is_en_passant() {
+        SEE_VALUES[Piece::Pawn as usize]
+    } else {
+        position.get(mov.target()).piece().map_or(0, |piece| SEE_VALUES[piece as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+
+    use super::*;
+    use crate::evaluation::evaluate;
+    use crate::search::INFINITY;
+
+    fn quiesce(fen: &str) -> Score {
+        let mut position = Position::new(BEN::new(fen));
+        Search::new().quiesce(&mut position, 0, -INFINITY, INFINITY)
+    }
+
+    #[test]
+    fn quiet_positions_stand_pat() {
+        let fen = "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1";
+        assert_eq!(quiesce(fen), evaluate(&Position::new(BEN::new(fen))));
+    }
+
+    #[test]
+    fn takes_what_is_hanging() {
+        let fen = "4k3/8/8/3q4/8/8/3R4/4K3 w - - 0 1";
+        assert!(quiesce(fen) > evaluate(&Position::new(BEN::new(fen))) + 500);
+    }
+
+    #[test]
+    fn does_not_count_a_defended_pawn_as_won() {
+        // Qxd5 exd5 just drops the queen, so standing pat is best.
+        let fen = "4k3/8/4p3/3p4/8/8/8/3QK3 w - - 0 1";
+        assert_eq!(quiesce(fen), evaluate(&Position::new(BEN::new(fen))));
+    }
+
+    #[test]
+    fn sees_mate_when_in_check() {
+        // Black is mated, with nothing to capture.
+        assert_eq!(quiesce("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), -MATE);
+    }
+}
@@ -0,0 +1,257 @@
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+
+use hazel_core::color::Color;
+use hazel_representation::coup::rep::Move;
+
+use crate::uci::GoParams;
+
+use super::SearchResult;
+
+/// Kept back from every allocation to cover the time it takes to talk to the GUI.
+pub const MOVE_OVERHEAD: Duration = Duration::from_millis(30);
+
+/// When the GUI doesn't say how many moves are left until the next time control, assume this many.
+pub const DEFAULT_MOVES_TO_GO: u32 = 30;
+
+/// Decides how long to think about a move.
+///
+/// There are two limits:
+///
+/// - The soft limit is checked between iterations. It's stretched when the best move keeps
+///   changing, and shrunk once it's settled, see `keep_going`.
+/// - The hard limit is never passed. The search doesn't check it itself, whoever runs the search
+///   has to stop it when it's reached (e.g., the `Deadline` message).
+///
+/// While pondering, neither limit applies, we're thinking on the opponent's time. Once the
+/// ponderhit arrives the limits kick in, counted from then rather than from the start.
+#[derive(Debug, Clone)]
+pub struct TimeManager {
+    start: Instant,
+    soft: Option<Duration>,
+    hard: Option<Duration>,
+    best_move: Option<Move>,
+    // How many iterations in a row the best move has stayed the same.
+    stability: u32,
+    // Set when the GUI sends `ponderhit`, if we're pondering at all.
+    ponderhit: Option<Arc<OnceLock<Instant>>>,
+}
+
+impl Default for TimeManager {
+    fn default() -> Self {
+        Self::unlimited()
+    }
+}
+
+impl TimeManager {
+    /// No limits at all, for `go infinite` and fixed depth or node searches.
+    pub fn unlimited() -> Self {
+        Self::with_limits(None, None)
+    }
+
+    pub fn with_limits(soft: Option<Duration>, hard: Option<Duration>) -> Self {
+        Self { start: Instant::now(), soft, hard, best_move: None, stability: 0, ponderhit: None }
+    }
+
+    /// Don't stop until `ponderhit` has been set, then stick to the limits from there.
+    ///
+    /// NOTE: `new` treats `go ponder` as unlimited, so to ponder with a clock, build the manager
+    /// as if `ponder` weren't there and then add this.
+    pub fn with_ponder(mut self, ponderhit: Arc<OnceLock<Instant>>) -> Self {
+        self.ponderhit = Some(ponderhit);
+        self
+    }
+
+    pub fn is_pondering(&self) -> bool {
+        self.ponderhit.as_ref().is_some_and(|hit| hit.get().is_none())
+    }
+
+    pub fn new(params: &GoParams, hero: Color) -> Self {
+        if params.infinite || params.ponder {
+            return Self::unlimited();
+        }
+
+        if let Some(movetime) = params.movetime {
+            let limit = after_overhead(movetime).max(Duration::from_millis(1));
+            return Self::with_limits(Some(limit), Some(limit));
+        }
+
+        let (time, inc) = match hero {
+            Color::WHITE => (params.wtime, params.winc),
+            Color::BLACK => (params.btime, params.binc),
+        };
+
+        let Some(time) = time else { return Self::unlimited(); };
+        let time = after_overhead(time);
+        let inc = Duration::from_millis(inc.unwrap_or(0).max(0) as u64);
+        let moves_to_go = params.movestogo.unwrap_or(DEFAULT_MOVES_TO_GO).clamp(1, 50);
+
+        let soft = (time / moves_to_go + inc * 3 / 4).min(time * 6 / 10);
+        let hard = (soft * 3).min(time * 8 / 10);
+
+        Self::with_limits(Some(soft), Some(hard))
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.hard.is_some()
+    }
+
+    pub fn soft_limit(&self) -> Option<Duration> {
+        self.soft
+    }
+
+    pub fn hard_limit(&self) -> Option<Duration> {
+        self.hard
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        match self.ponderhit.as_ref().and_then(|hit| hit.get()) {
+            Some(hit) => hit.elapsed(),
+            None => self.start.elapsed(),
+        }
+    }
+
+    /// Called after each iteration, returns whether it's worth starting another.
+    pub fn keep_going(&mut self, result: &SearchResult) -> bool {
+        if result.best_move().is_some() && result.best_move() == self.best_move {
+            self.stability += 1;
+        } else {
+            self.stability = 0;
+            self.best_move = result.best_move();
+        }
+
+        if self.is_pondering() { return true; }
+
+        match self.adjusted_soft_limit() {
+            Some(limit) => self.elapsed() < limit,
+            None => true,
+        }
+    }
+
+    /// The soft limit, stretched while the best move is unsettled, and cut short once it's held
+    /// for a few iterations. Never more than the hard limit.
+    pub fn adjusted_soft_limit(&self) -> Option<Duration> {
+        let soft = self.soft?;
+        let scaled = match self.stability {
+            0 => soft * 3 / 2,
+            1 => soft,
+            2 => soft * 4 / 5,
+            _ => soft * 3 / 5,
+        };
+
+        Some(match self.hard {
+            Some(hard) => scaled.min(hard),
+            None => scaled,
+        })
+    }
+}
+
+fn after_overhead(millis: i64) -> Duration {
+    Duration::from_millis(millis.max(0) as u64).saturating_sub(MOVE_OVERHEAD)
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    fn manager(go: &str, hero: Color) -> TimeManager {
+        TimeManager::new(&go.parse().unwrap(), hero)
+    }
+
+    fn best(mov: Move) -> SearchResult {
+        SearchResult { pv: vec![mov], ..Default::default() }
+    }
+
+    #[test]
+    fn unlimited_searches() {
+        for go in ["", "infinite", "depth 5", "nodes 1000", "ponder wtime 1000 btime 1000"] {
+            assert!(!manager(go, Color::WHITE).is_limited(), "{go}");
+        }
+    }
+
+    #[test]
+    fn movetime_is_both_limits() {
+        let time = manager("movetime 1000", Color::WHITE);
+        assert_eq!(time.soft_limit(), Some(Duration::from_millis(1000) - MOVE_OVERHEAD));
+        assert_eq!(time.hard_limit(), time.soft_limit());
+    }
+
+    #[test]
+    fn uses_the_clock_of_the_side_to_move() {
+        let white = manager("wtime 60000 btime 1000", Color::WHITE);
+        let black = manager("wtime 60000 btime 1000", Color::BLACK);
+
+        assert!(white.soft_limit() > black.soft_limit());
+        assert!(white.hard_limit().unwrap() < Duration::from_millis(60_000));
+        assert!(black.hard_limit().unwrap() < Duration::from_millis(1000));
+    }
+
+    #[test]
+    fn increments_and_moves_to_go() {
+        let base = manager("wtime 60000 btime 60000", Color::WHITE).soft_limit().unwrap();
+        let inc = manager("wtime 60000 btime 60000 winc 1000", Color::WHITE).soft_limit().unwrap();
+        let last_move = manager("wtime 60000 btime 60000 movestogo 1", Color::WHITE).soft_limit().unwrap();
+
+        assert!(inc > base);
+        assert!(last_move > inc);
+        // Even on the last move before the time control, some time is kept back.
+        assert!(last_move < Duration::from_millis(60_000));
+    }
+
+    #[test]
+    fn soft_is_never_more_than_hard() {
+        for go in ["wtime 100 btime 100", "wtime 0 btime 0", "wtime -50 btime 10 winc 5000", "wtime 300000 btime 300000 movestogo 1"] {
+            let time = manager(go, Color::WHITE);
+            assert!(time.soft_limit() <= time.hard_limit(), "{go}");
+        }
+    }
+
+    #[test]
+    fn stable_best_moves_finish_early() {
+        let mut time = TimeManager::with_limits(Some(Duration::from_secs(10)), Some(Duration::from_secs(30)));
+        let e4 = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+        let d4 = Move::new(D2, D4, MoveType::DOUBLE_PAWN);
+
+        assert!(time.keep_going(&best(e4)));
+        assert_eq!(time.adjusted_soft_limit(), Some(Duration::from_secs(15)));
+
+        time.keep_going(&best(e4));
+        time.keep_going(&best(e4));
+        time.keep_going(&best(e4));
+        assert_eq!(time.adjusted_soft_limit(), Some(Duration::from_secs(6)));
+
+        // A change of mind buys more time.
+        time.keep_going(&best(d4));
+        assert_eq!(time.adjusted_soft_limit(), Some(Duration::from_secs(15)));
+    }
+
+    #[test]
+    fn pondering_ignores_the_clock_until_ponderhit() {
+        let ponderhit = Arc::new(OnceLock::new());
+        let mut time = TimeManager::with_limits(Some(Duration::from_millis(20)), Some(Duration::from_millis(40)))
+            .with_ponder(ponderhit.clone());
+        let e4 = best(Move::new(E2, E4, MoveType::DOUBLE_PAWN));
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(time.is_pondering());
+        assert!(time.keep_going(&e4));
+
+        // The clock starts at the ponderhit, not when we started pondering.
+        ponderhit.set(Instant::now()).unwrap();
+        assert!(!time.is_pondering());
+        assert!(time.elapsed() < Duration::from_millis(20));
+        assert!(time.keep_going(&e4));
+
+        std::thread::sleep(Duration::from_millis(50));
+        assert!(!time.keep_going(&e4));
+    }
+
+    #[test]
+    fn out_of_time_stops() {
+        let mut time = TimeManager::with_limits(Some(Duration::ZERO), Some(Duration::ZERO));
+        assert!(!time.keep_going(&best(Move::new(E2, E4, MoveType::DOUBLE_PAWN))));
+    }
+}
@@ -0,0 +1,284 @@
+use std::fmt::Debug;
+use std::sync::RwLock;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+use hazel_core::zobrist::Zobrist;
+use hazel_representation::coup::rep::Move;
+
+use super::{is_mate, Score};
+
+/// Size of the table, in megabytes, if nobody sets the `Hash` option.
+pub const DEFAULT_HASH_MB: usize = 16;
+/// The biggest table the `Hash` option allows.
+pub const MAX_HASH_MB: usize = 4096;
+
+/// What the stored score says about the true score of the position.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bound {
+    /// It is the score.
+    Exact,
+    /// The search failed high, the true score is at least this.
+    Lower,
+    /// The search failed low, the true score is at most this.
+    Upper,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub key: Zobrist,
+    pub depth: u8,
+    pub bound: Bound,
+    /// Mate scores are stored relative to this position, not the root, see `Entry::score`.
+    score: Score,
+    pub best_move: Option<Move>,
+    /// Which search wrote this, entries from old searches are the first to be replaced.
+    pub age: u8,
+}
+
+impl Entry {
+    /// The stored score, as seen from `ply` plies below the root.
+    pub fn score(&self, ply: usize) -> Score {
+        to_root(self.score, ply)
+    }
+}
+
+/// Two entries per bucket. The first only gets replaced by something searched at least as deep
+/// (or by anything, once it's stale), the second is replaced every time the first isn't.
+type Bucket = [Option<Entry>; 2];
+
+const DEPTH_PREFERRED: usize = 0;
+const ALWAYS_REPLACE: usize = 1;
+
+/// A fixed size hash table of search results, indexed by the low bits of the position's zobrist.
+/// The number of buckets is always a power of two.
+///
+/// Like `Cache`, everything goes through `&self`, so it can be shared between searches behind an
+/// `Arc`.
+pub struct TranspositionTable {
+    buckets: RwLock<Vec<Bucket>>,
+    age: AtomicU8,
+}
+
+impl Default for TranspositionTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_HASH_MB)
+    }
+}
+
+// The table itself is far too big to print.
+impl Debug for TranspositionTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TranspositionTable({} buckets, age {})", self.capacity(), self.age())
+    }
+}
+
+impl TranspositionTable {
+    pub fn new(megabytes: usize) -> Self {
+        Self {
+            buckets: RwLock::new(vec![[None; 2]; Self::buckets_for(megabytes)]),
+            age: AtomicU8::new(0),
+        }
+    }
+
+    /// The largest power of two number of buckets which fits, but always at least one.
+    fn buckets_for(megabytes: usize) -> usize {
+        let count = (megabytes * 1024 * 1024) / std::mem::size_of::<Bucket>();
+        if count == 0 { 1 } else { 1 << count.ilog2() }
+    }
+
+    /// Number of buckets.
+    pub fn capacity(&self) -> usize {
+        self.buckets.read().unwrap().len()
+    }
+
+    pub fn age(&self) -> u8 {
+        self.age.load(Ordering::Relaxed)
+    }
+
+    /// Throws everything away and reallocates at the new size.
+    pub fn resize(&self, megabytes: usize) {
+        *self.buckets.write().unwrap() = vec![[None; 2]; Self::buckets_for(megabytes)];
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    pub fn clear(&self) {
+        self.buckets.write().unwrap().fill([None; 2]);
+        self.age.store(0, Ordering::Relaxed);
+    }
+
+    /// Call at the start of each search, so the last search's entries become fair game.
+    pub fn new_search(&self) {
+        self.age.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn index(key: Zobrist, len: usize) -> usize {
+        (key.inner() as usize) & (len - 1)
+    }
+
+    pub fn probe(&self, key: Zobrist) -> Option<Entry> {
+        let buckets = self.buckets.read().unwrap();
+        buckets[Self::index(key, buckets.len())].iter().flatten().find(|entry| entry.key == key).copied()
+    }
+
+    /// `score` is relative to the root, `ply` is how far below the root we are.
+    pub fn store(&self, key: Zobrist, depth: usize, ply: usize, bound: Bound, score: Score, best_move: Option<Move>) {
+        let age = self.age();
+        let mut buckets = self.buckets.write().unwrap();
+        let len = buckets.len();
+        let bucket = &mut buckets[Self::index(key, len)];
+
+        // A new search of the same position without a move shouldn't forget the one we had.
+        let best_move = best_move.or_else(|| bucket.iter().flatten().find(|entry| entry.key == key).and_then(|entry| entry.best_move));
+
+        let entry = Entry {
+            key,
+            depth: depth.min(u8::MAX as usize) as u8,
+            bound,
+            score: from_root(score, ply),
+            best_move,
+            age,
+        };
+
+        let replace_deep = match bucket[DEPTH_PREFERRED] {
+            None => true,
+            Some(old) => old.key == key || old.age != age || entry.depth >= old.depth,
+        };
+
+        if replace_deep {
+            // If the deep entry was for some other position, it's still worth keeping around.
+            if let Some(old) = bucket[DEPTH_PREFERRED] {
+                if old.key != key { bucket[ALWAYS_REPLACE] = Some(old); }
+            }
+            if bucket[ALWAYS_REPLACE].is_some_and(|old| old.key == key) {
+                bucket[ALWAYS_REPLACE] = None;
+            }
+            bucket[DEPTH_PREFERRED] = Some(entry);
+        } else {
+            bucket[ALWAYS_REPLACE] = Some(entry);
+        }
+    }
+
+    /// How full the table is, in permille, for `info hashfull`. Only the current search's entries
+    /// count, and only the first thousand buckets are looked at.
+    pub fn hashfull(&self) -> usize {
+        let age = self.age();
+        let buckets = self.buckets.read().unwrap();
+        let sample = &buckets[..buckets.len().min(1000)];
+        let used = sample.iter().flatten().flatten().filter(|entry| entry.age == age).count();
+
+        used * 1000 / (sample.len() * 2)
+    }
+}
+
+// Mate scores count plies from the root, but the same position can turn up at any ply, so the
+// table stores them counted from the position instead.
+fn from_root(score: Score, ply: usize) -> Score {
+    if !is_mate(score) { score } else if score > 0 { score + ply as Score } else { score - ply as Score }
+}
+
+fn to_root(score: Score, ply: usize) -> Score {
+    if !is_mate(score) { score } else if score > 0 { score - ply as Score } else { score + ply as Score }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+    use crate::search::MATE;
+
+    fn key(v: u64) -> Zobrist {
+        Zobrist::from(v)
+    }
+
+    #[test]
+    fn capacity_is_a_power_of_two() {
+        for mb in [0, 1, 3, 16, 100] {
+            let capacity = TranspositionTable::new(mb).capacity();
+            assert!(capacity.is_power_of_two(), "{mb}MB -> {capacity}");
+            assert!(capacity * std::mem::size_of::<Bucket>() <= (mb * 1024 * 1024).max(std::mem::size_of::<Bucket>()));
+        }
+    }
+
+    #[test]
+    fn stores_and_probes() {
+        let table = TranspositionTable::new(1);
+        let mov = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+
+        assert_eq!(table.probe(key(42)), None);
+        table.store(key(42), 3, 0, Bound::Exact, 17, Some(mov));
+
+        let entry = table.probe(key(42)).unwrap();
+        assert_eq!(entry.depth, 3);
+        assert_eq!(entry.bound, Bound::Exact);
+        assert_eq!(entry.score(0), 17);
+        assert_eq!(entry.best_move, Some(mov));
+    }
+
+    #[test]
+    fn mate_scores_are_stored_relative_to_the_position() {
+        let table = TranspositionTable::new(1);
+        // Mate found 3 plies below the root, 5 plies from the root in total.
+        table.store(key(1), 2, 3, Bound::Exact, MATE - 5, None);
+
+        // So it's mate 2 plies on from the position, and reached again 1 ply below the root, 3
+        // from the root.
+        assert_eq!(table.probe(key(1)).unwrap().score(1), MATE - 3);
+    }
+
+    #[test]
+    fn deep_entries_survive_shallow_ones() {
+        let table = TranspositionTable::new(0);
+        assert_eq!(table.capacity(), 1);
+
+        table.store(key(1), 8, 0, Bound::Lower, 10, None);
+        table.store(key(2), 2, 0, Bound::Upper, 20, None);
+        table.store(key(3), 1, 0, Bound::Upper, 30, None);
+
+        // The deep one stays, the always-replace slot holds the latest.
+        assert!(table.probe(key(1)).is_some());
+        assert!(table.probe(key(2)).is_none());
+        assert!(table.probe(key(3)).is_some());
+
+        // Deeper still takes the slot, and the old deep entry moves over.
+        table.store(key(4), 9, 0, Bound::Exact, 40, None);
+        assert!(table.probe(key(4)).is_some());
+        assert!(table.probe(key(1)).is_some());
+        assert!(table.probe(key(3)).is_none());
+    }
+
+    #[test]
+    fn stale_entries_are_replaced() {
+        let table = TranspositionTable::new(0);
+        table.store(key(1), 8, 0, Bound::Exact, 10, None);
+        table.new_search();
+        table.store(key(2), 1, 0, Bound::Exact, 20, None);
+
+        assert_eq!(table.probe(key(2)).unwrap().depth, 1);
+        assert_eq!(table.probe(key(1)).unwrap().depth, 8);
+    }
+
+    #[test]
+    fn keeps_the_best_move_on_a_moveless_store() {
+        let table = TranspositionTable::new(1);
+        let mov = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+        table.store(key(5), 1, 0, Bound::Lower, 10, Some(mov));
+        table.store(key(5), 2, 0, Bound::Upper, -10, None);
+
+        let entry = table.probe(key(5)).unwrap();
+        assert_eq!(entry.depth, 2);
+        assert_eq!(entry.best_move, Some(mov));
+    }
+
+    #[test]
+    fn clear_and_hashfull() {
+        let table = TranspositionTable::new(0);
+        table.store(key(1), 1, 0, Bound::Exact, 0, None);
+        assert_eq!(table.hashfull(), 500);
+
+        table.clear();
+        assert_eq!(table.probe(key(1)), None);
+        assert_eq!(table.hashfull(), 0);
+    }
+}
@@ -45,7 +45,11 @@ where T: 'static + io::Read + Send + Unpin,
         let mut line = String::new();
         _ = input.read_line(&mut line);
         let message = UCIMessage::parse(&line);
+        let quit = message == UCIMessage::Quit;
         hazel.send(Box::new(message)).await;
+
+        // Hazel has stopped any search, there's no need to wait on anything else.
+        if quit { return Ok(()); }
     }
 }
 
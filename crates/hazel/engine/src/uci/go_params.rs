@@ -0,0 +1,235 @@
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+use hazel_parser::uci::UCI;
+use hazel_representation::coup::rep::Move;
+
+/// The arguments to `go`, parsed.
+///
+/// Times are in milliseconds. They're signed, since some GUIs will send a negative clock when
+/// someone is out of time, and it's better to search quickly than to refuse to search.
+///
+/// NOTE: `searchmoves` come straight from UCI, so they're ambiguous (no capture or castling
+/// flags, see `MoveGenerator::is_legal`) until resolved against a position.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct GoParams {
+    pub searchmoves: Vec<Move>,
+    pub ponder: bool,
+    pub wtime: Option<i64>,
+    pub btime: Option<i64>,
+    pub winc: Option<i64>,
+    pub binc: Option<i64>,
+    pub movestogo: Option<u32>,
+    pub depth: Option<usize>,
+    pub nodes: Option<usize>,
+    pub mate: Option<u32>,
+    pub movetime: Option<i64>,
+    pub infinite: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GoParamsError {
+    /// A parameter which needs a value was the last thing on the line.
+    MissingValue(String),
+    /// A parameter's value didn't parse, e.g. `depth ten`. Holds the parameter and the value.
+    InvalidValue(String, String),
+    /// Something in `searchmoves` wasn't a UCI move.
+    InvalidMove(String),
+    /// `searchmoves` with no moves after it.
+    EmptySearchMoves,
+    UnknownParameter(String),
+}
+
+impl Display for GoParamsError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            GoParamsError::MissingValue(param) => write!(f, "go: {} needs a value", param),
+            GoParamsError::InvalidValue(param, value) => write!(f, "go: {:?} is not a valid value for {}", value, param),
+            GoParamsError::InvalidMove(mov) => write!(f, "go: {:?} is not a move", mov),
+            GoParamsError::EmptySearchMoves => write!(f, "go: searchmoves needs at least one move"),
+            GoParamsError::UnknownParameter(param) => write!(f, "go: unknown parameter {:?}", param),
+        }
+    }
+}
+
+const KEYWORDS: [&str; 12] = [
+    "searchmoves", "ponder", "wtime", "btime", "winc", "binc", "movestogo", "depth", "nodes", "mate", "movetime", "infinite"
+];
+
+impl GoParams {
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Result<Self, GoParamsError> {
+        let mut params = GoParams::default();
+        let mut args = args.iter().map(|arg| arg.as_ref()).peekable();
+
+        while let Some(param) = args.next() {
+            match param {
+                "ponder" => params.ponder = true,
+                "infinite" => params.infinite = true,
+                "searchmoves" => {
+                    while let Some(mov) = args.next_if(|arg| !KEYWORDS.contains(arg)) {
+                        let uci = UCI::try_from(mov).map_err(|_| GoParamsError::InvalidMove(mov.to_string()))?;
+                        params.searchmoves.push(uci.into());
+                    }
+                    if params.searchmoves.is_empty() { return Err(GoParamsError::EmptySearchMoves); }
+                }
+                "wtime" => params.wtime = Some(value(param, args.next())?),
+                "btime" => params.btime = Some(value(param, args.next())?),
+                "winc" => params.winc = Some(value(param, args.next())?),
+                "binc" => params.binc = Some(value(param, args.next())?),
+                "movestogo" => params.movestogo = Some(value(param, args.next())?),
+                "depth" => params.depth = Some(value(param, args.next())?),
+                "nodes" => params.nodes = Some(value(param, args.next())?),
+                "mate" => params.mate = Some(value(param, args.next())?),
+                "movetime" => params.movetime = Some(value(param, args.next())?),
+                unknown => return Err(GoParamsError::UnknownParameter(unknown.to_string())),
+            }
+        }
+
+        Ok(params)
+    }
+}
+
+fn value<T: FromStr>(param: &str, value: Option<&str>) -> Result<T, GoParamsError> {
+    let Some(value) = value else { return Err(GoParamsError::MissingValue(param.to_string())); };
+    value.parse().map_err(|_| GoParamsError::InvalidValue(param.to_string(), value.to_string()))
+}
+
+impl FromStr for GoParams {
+    type Err = GoParamsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(&s.split_whitespace().collect::<Vec<&str>>())
+    }
+}
+
+impl TryFrom<&[String]> for GoParams {
+    type Error = GoParamsError;
+
+    fn try_from(args: &[String]) -> Result<Self, Self::Error> {
+        Self::parse(args)
+    }
+}
+
+/// The arguments only, in the order the spec lists them, so `go {params}` is the full command.
+impl Display for GoParams {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut parts = vec![];
+
+        if !self.searchmoves.is_empty() {
+            parts.push("searchmoves".to_string());
+            parts.extend(self.searchmoves.iter().map(|mov| mov.to_uci()));
+        }
+        if self.ponder { parts.push("ponder".to_string()); }
+
+        let numbers = [
+            ("wtime", self.wtime.map(|v| v.to_string())),
+            ("btime", self.btime.map(|v| v.to_string())),
+            ("winc", self.winc.map(|v| v.to_string())),
+            ("binc", self.binc.map(|v| v.to_string())),
+            ("movestogo", self.movestogo.map(|v| v.to_string())),
+            ("depth", self.depth.map(|v| v.to_string())),
+            ("nodes", self.nodes.map(|v| v.to_string())),
+            ("mate", self.mate.map(|v| v.to_string())),
+            ("movetime", self.movetime.map(|v| v.to_string())),
+        ];
+        for (name, value) in numbers {
+            if let Some(value) = value {
+                parts.push(name.to_string());
+                parts.push(value);
+            }
+        }
+
+        if self.infinite { parts.push("infinite".to_string()); }
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    fn parse(s: &str) -> Result<GoParams, GoParamsError> {
+        s.parse()
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(parse(""), Ok(GoParams::default()));
+    }
+
+    #[test]
+    fn clock() {
+        let params = parse("wtime 300000 btime 299000 winc 2000 binc 2000 movestogo 40").unwrap();
+        assert_eq!(params.wtime, Some(300_000));
+        assert_eq!(params.btime, Some(299_000));
+        assert_eq!(params.winc, Some(2000));
+        assert_eq!(params.binc, Some(2000));
+        assert_eq!(params.movestogo, Some(40));
+    }
+
+    #[test]
+    fn limits() {
+        let params = parse("depth 6 nodes 10000 mate 3 movetime 500").unwrap();
+        assert_eq!(params.depth, Some(6));
+        assert_eq!(params.nodes, Some(10_000));
+        assert_eq!(params.mate, Some(3));
+        assert_eq!(params.movetime, Some(500));
+    }
+
+    #[test]
+    fn flags() {
+        let params = parse("ponder infinite").unwrap();
+        assert!(params.ponder);
+        assert!(params.infinite);
+    }
+
+    #[test]
+    fn negative_clocks_are_allowed() {
+        assert_eq!(parse("wtime -120").unwrap().wtime, Some(-120));
+    }
+
+    #[test]
+    fn searchmoves_stop_at_the_next_keyword() {
+        let params = parse("searchmoves e2e4 d2d4 e7e8q depth 3").unwrap();
+        assert_eq!(params.searchmoves, vec![
+            Move::new(E2, E4, MoveType::UCI_AMBIGUOUS),
+            Move::new(D2, D4, MoveType::UCI_AMBIGUOUS),
+            Move::new(E7, E8, MoveType::PROMOTION_QUEEN),
+        ]);
+        assert_eq!(params.depth, Some(3));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("depth"), Err(GoParamsError::MissingValue("depth".to_string())));
+        assert_eq!(parse("depth ten"), Err(GoParamsError::InvalidValue("depth".to_string(), "ten".to_string())));
+        assert_eq!(parse("depth -1"), Err(GoParamsError::InvalidValue("depth".to_string(), "-1".to_string())));
+        assert_eq!(parse("searchmoves e2e9"), Err(GoParamsError::InvalidMove("e2e9".to_string())));
+        assert_eq!(parse("searchmoves depth 2"), Err(GoParamsError::EmptySearchMoves));
+        assert_eq!(parse("sideways 3"), Err(GoParamsError::UnknownParameter("sideways".to_string())));
+    }
+
+    #[test]
+    fn round_trips() {
+        for s in [
+            "",
+            "infinite",
+            "searchmoves e2e4 e7e8n ponder wtime 1000 btime 1000 winc 10 binc 10 movestogo 5",
+            "depth 3 nodes 100 mate 2 movetime 50",
+        ] {
+            let params = parse(s).unwrap();
+            assert_eq!(params.to_string(), s);
+            assert_eq!(parse(&params.to_string()), Ok(params));
+        }
+    }
+
+    #[test]
+    fn from_go_message() {
+        let args = vec!["depth".to_string(), "2".to_string()];
+        assert_eq!(GoParams::try_from(args.as_slice()).unwrap().depth, Some(2));
+    }
+}
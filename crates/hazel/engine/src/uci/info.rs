@@ -0,0 +1,342 @@
+use std::fmt::{self, Display, Formatter};
+use std::iter::Peekable;
+use std::str::FromStr;
+
+use hazel_parser::uci::UCI;
+use hazel_representation::coup::rep::Move;
+
+/// The score in an `info` line, from the engine's point of view.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UCIScore {
+    Centipawns(i32),
+    /// Moves (not plies) to mate, negative if the engine is getting mated.
+    Mate(i32),
+}
+
+/// Whether the score in an `info` line is the real thing, or only a bound on it.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum UCIBound {
+    #[default] Exact,
+    /// `lowerbound`, the true score is at least this.
+    Lower,
+    /// `upperbound`, the true score is at most this.
+    Upper,
+}
+
+/// The arguments to `info`, parsed.
+///
+/// Everything is optional, engines send whatever they like, whenever they like. Times are in
+/// milliseconds, `hashfull` and `cpuload` are in permille. Parameters we don't know about are
+/// skipped, along with their values.
+///
+/// NOTE: Moves come straight from UCI, so they're ambiguous (no capture or castling flags, see
+/// `MoveGenerator::is_legal`) until resolved against a position.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct UCIInfo {
+    pub depth: Option<usize>,
+    pub seldepth: Option<usize>,
+    pub multipv: Option<usize>,
+    pub score: Option<UCIScore>,
+    /// Whether `score` is only a bound (`lowerbound` or `upperbound`), or the real thing.
+    pub bound: UCIBound,
+    /// Stockfish's win/draw/loss chances in permille, with `UCI_ShowWDL` on.
+    pub wdl: Option<(u32, u32, u32)>,
+    pub nodes: Option<usize>,
+    pub nps: Option<u64>,
+    pub hashfull: Option<u32>,
+    pub tbhits: Option<u64>,
+    pub cpuload: Option<u32>,
+    pub time: Option<u64>,
+    pub currmove: Option<Move>,
+    pub currmovenumber: Option<usize>,
+    pub pv: Vec<Move>,
+    /// The line which refutes the first move.
+    pub refutation: Vec<Move>,
+    /// The line being searched right now.
+    ///
+    /// NOTE: This can start with the number of the CPU searching it, we don't keep that.
+    pub currline: Vec<Move>,
+    /// Free text, always the rest of the line.
+    pub string: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UCIInfoError {
+    /// A parameter which needs a value was the last thing on the line.
+    MissingValue(String),
+    /// A parameter's value didn't parse, e.g. `depth ten`. Holds the parameter and the value.
+    InvalidValue(String, String),
+    /// Something in `pv` or `currmove` wasn't a UCI move.
+    InvalidMove(String),
+}
+
+impl Display for UCIInfoError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            UCIInfoError::MissingValue(param) => write!(f, "info: {} needs a value", param),
+            UCIInfoError::InvalidValue(param, value) => write!(f, "info: {:?} is not a valid value for {}", value, param),
+            UCIInfoError::InvalidMove(mov) => write!(f, "info: {:?} is not a move", mov),
+        }
+    }
+}
+
+const KEYWORDS: [&str; 17] = [
+    "depth", "seldepth", "multipv", "score", "wdl", "nodes", "nps", "hashfull", "tbhits", "cpuload",
+    "time", "currmove", "currmovenumber", "pv", "refutation", "currline", "string"
+];
+
+impl UCIInfo {
+    pub fn parse<S: AsRef<str>>(args: &[S]) -> Result<Self, UCIInfoError> {
+        let mut info = UCIInfo::default();
+        info.parse_into(args)?;
+        Ok(info)
+    }
+
+    /// Like `parse`, but fills in `self` as it goes, so if something is wrong with the line
+    /// everything before the bad parameter is kept.
+    pub fn parse_into<S: AsRef<str>>(&mut self, args: &[S]) -> Result<(), UCIInfoError> {
+        let mut args = args.iter().map(|arg| arg.as_ref()).peekable();
+
+        while let Some(param) = args.next() {
+            match param {
+                "depth" => self.depth = Some(value(param, args.next())?),
+                "seldepth" => self.seldepth = Some(value(param, args.next())?),
+                "multipv" => self.multipv = Some(value(param, args.next())?),
+                "nodes" => self.nodes = Some(value(param, args.next())?),
+                "nps" => self.nps = Some(value(param, args.next())?),
+                "hashfull" => self.hashfull = Some(value(param, args.next())?),
+                "tbhits" => self.tbhits = Some(value(param, args.next())?),
+                "cpuload" => self.cpuload = Some(value(param, args.next())?),
+                "time" => self.time = Some(value(param, args.next())?),
+                "currmovenumber" => self.currmovenumber = Some(value(param, args.next())?),
+                "currmove" => {
+                    let Some(mov) = args.next() else { return Err(UCIInfoError::MissingValue(param.to_string())); };
+                    self.currmove = Some(parse_move(mov)?);
+                }
+                "score" => {
+                    self.score = Some(match (args.next(), args.next()) {
+                        (Some("cp"), Some(cp)) => UCIScore::Centipawns(value("score cp", Some(cp))?),
+                        (Some("mate"), Some(moves)) => UCIScore::Mate(value("score mate", Some(moves))?),
+                        (Some(kind @ ("cp" | "mate")), None) => return Err(UCIInfoError::MissingValue(format!("score {}", kind))),
+                        (Some(kind), _) => return Err(UCIInfoError::InvalidValue(param.to_string(), kind.to_string())),
+                        (None, _) => return Err(UCIInfoError::MissingValue(param.to_string())),
+                    });
+                    self.bound = match args.next_if(|arg| *arg == "lowerbound" || *arg == "upperbound") {
+                        Some("lowerbound") => UCIBound::Lower,
+                        Some(_) => UCIBound::Upper,
+                        None => UCIBound::Exact,
+                    };
+                }
+                "wdl" => {
+                    self.wdl = Some((value(param, args.next())?, value(param, args.next())?, value(param, args.next())?));
+                }
+                "pv" => self.pv = moves(&mut args)?,
+                "refutation" => self.refutation = moves(&mut args)?,
+                "currline" => {
+                    _ = args.next_if(|arg| arg.parse::<usize>().is_ok());
+                    self.currline = moves(&mut args)?;
+                }
+                "string" => {
+                    self.string = Some(args.by_ref().collect::<Vec<&str>>().join(" "));
+                }
+                unknown => {
+                    tracing::debug!("info: skipping unknown parameter {:?}", unknown);
+                    while args.next_if(|arg| !KEYWORDS.contains(arg)).is_some() {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn value<T: FromStr>(param: &str, value: Option<&str>) -> Result<T, UCIInfoError> {
+    let Some(value) = value else { return Err(UCIInfoError::MissingValue(param.to_string())); };
+    value.parse().map_err(|_| UCIInfoError::InvalidValue(param.to_string(), value.to_string()))
+}
+
+/// Moves up to the next parameter.
+fn moves<'a>(args: &mut Peekable<impl Iterator<Item = &'a str>>) -> Result<Vec<Move>, UCIInfoError> {
+    let mut moves = vec![];
+    while let Some(mov) = args.next_if(|arg| !KEYWORDS.contains(arg)) {
+        moves.push(parse_move(mov)?);
+    }
+    Ok(moves)
+}
+
+fn parse_move(mov: &str) -> Result<Move, UCIInfoError> {
+    UCI::try_from(mov).map(Move::from).map_err(|_| UCIInfoError::InvalidMove(mov.to_string()))
+}
+
+impl FromStr for UCIInfo {
+    type Err = UCIInfoError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(&s.split_whitespace().collect::<Vec<&str>>())
+    }
+}
+
+/// The arguments only, in the order Stockfish sends them, so `info {info}` is the full line.
+impl Display for UCIInfo {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut parts = vec![];
+
+        push(&mut parts, "depth", self.depth);
+        push(&mut parts, "seldepth", self.seldepth);
+        push(&mut parts, "multipv", self.multipv);
+
+        if let Some(score) = self.score {
+            let score = match score {
+                UCIScore::Centipawns(cp) => format!("score cp {}", cp),
+                UCIScore::Mate(moves) => format!("score mate {}", moves),
+            };
+            parts.push(match self.bound {
+                UCIBound::Exact => score,
+                UCIBound::Lower => format!("{} lowerbound", score),
+                UCIBound::Upper => format!("{} upperbound", score),
+            });
+        }
+        push(&mut parts, "wdl", self.wdl.map(|(w, d, l)| format!("{} {} {}", w, d, l)));
+
+        push(&mut parts, "nodes", self.nodes);
+        push(&mut parts, "nps", self.nps);
+        push(&mut parts, "hashfull", self.hashfull);
+        push(&mut parts, "tbhits", self.tbhits);
+        push(&mut parts, "cpuload", self.cpuload);
+        push(&mut parts, "time", self.time);
+        push(&mut parts, "currmove", self.currmove.map(|mov| mov.to_uci()));
+        push(&mut parts, "currmovenumber", self.currmovenumber);
+
+        push_moves(&mut parts, "pv", &self.pv);
+        push_moves(&mut parts, "refutation", &self.refutation);
+        push_moves(&mut parts, "currline", &self.currline);
+        push(&mut parts, "string", self.string.as_ref());
+
+        write!(f, "{}", parts.join(" "))
+    }
+}
+
+fn push<T: Display>(parts: &mut Vec<String>, name: &str, value: Option<T>) {
+    if let Some(value) = value {
+        parts.push(format!("{} {}", name, value));
+    }
+}
+
+fn push_moves(parts: &mut Vec<String>, name: &str, moves: &[Move]) {
+    if !moves.is_empty() {
+        parts.push(format!("{} {}", name, moves.iter().map(|mov| mov.to_uci()).collect::<Vec<String>>().join(" ")));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    fn parse(s: &str) -> Result<UCIInfo, UCIInfoError> {
+        s.parse()
+    }
+
+    #[test]
+    fn stockfish_output() {
+        let info = parse("depth 12 seldepth 17 multipv 1 score cp 31 nodes 51042 nps 850700 hashfull 21 tbhits 0 time 60 pv e2e4 e7e5 g1f3").unwrap();
+
+        assert_eq!(info.depth, Some(12));
+        assert_eq!(info.seldepth, Some(17));
+        assert_eq!(info.multipv, Some(1));
+        assert_eq!(info.score, Some(UCIScore::Centipawns(31)));
+        assert_eq!(info.bound, UCIBound::Exact);
+        assert_eq!(info.nodes, Some(51_042));
+        assert_eq!(info.nps, Some(850_700));
+        assert_eq!(info.hashfull, Some(21));
+        assert_eq!(info.tbhits, Some(0));
+        assert_eq!(info.time, Some(60));
+        assert_eq!(info.pv, vec![
+            Move::new(E2, E4, MoveType::UCI_AMBIGUOUS),
+            Move::new(E7, E5, MoveType::UCI_AMBIGUOUS),
+            Move::new(G1, F3, MoveType::UCI_AMBIGUOUS),
+        ]);
+    }
+
+    #[test]
+    fn scores() {
+        assert_eq!(parse("score mate -3").unwrap().score, Some(UCIScore::Mate(-3)));
+
+        let info = parse("score cp 12 lowerbound nodes 5").unwrap();
+        assert_eq!(info.score, Some(UCIScore::Centipawns(12)));
+        assert_eq!(info.bound, UCIBound::Lower);
+        assert_eq!(info.nodes, Some(5));
+
+        assert_eq!(parse("score mate 2 upperbound").unwrap().bound, UCIBound::Upper);
+    }
+
+    #[test]
+    fn current_move() {
+        let info = parse("depth 5 currmove e7e8q currmovenumber 3").unwrap();
+        assert_eq!(info.currmove, Some(Move::new(E7, E8, MoveType::PROMOTION_QUEEN)));
+        assert_eq!(info.currmovenumber, Some(3));
+    }
+
+    #[test]
+    fn strings_are_the_rest_of_the_line() {
+        let info = parse("string NNUE evaluation using nn-b1a57edbea57.nnue depth 3").unwrap();
+        assert_eq!(info.string, Some("NNUE evaluation using nn-b1a57edbea57.nnue depth 3".to_string()));
+        assert_eq!(info.depth, None);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(parse("depth"), Err(UCIInfoError::MissingValue("depth".to_string())));
+        assert_eq!(parse("nodes lots"), Err(UCIInfoError::InvalidValue("nodes".to_string(), "lots".to_string())));
+        assert_eq!(parse("score"), Err(UCIInfoError::MissingValue("score".to_string())));
+        assert_eq!(parse("score cp"), Err(UCIInfoError::MissingValue("score cp".to_string())));
+        assert_eq!(parse("score pawns 3"), Err(UCIInfoError::InvalidValue("score".to_string(), "pawns".to_string())));
+        assert_eq!(parse("pv e2e4 e7e9"), Err(UCIInfoError::InvalidMove("e7e9".to_string())));
+        assert_eq!(parse("currmove"), Err(UCIInfoError::MissingValue("currmove".to_string())));
+        assert_eq!(parse("wdl 1 2"), Err(UCIInfoError::MissingValue("wdl".to_string())));
+        assert_eq!(parse("refutation d1h5 g6h9"), Err(UCIInfoError::InvalidMove("g6h9".to_string())));
+    }
+
+    #[test]
+    fn refutations_and_current_lines() {
+        let info = parse("depth 3 refutation d1h5 g6h5 currline 1 e2e4 e7e5 pv g1f3").unwrap();
+        assert_eq!(info.refutation, vec![Move::new(D1, H5, MoveType::UCI_AMBIGUOUS), Move::new(G6, H5, MoveType::UCI_AMBIGUOUS)]);
+        assert_eq!(info.currline, vec![Move::new(E2, E4, MoveType::UCI_AMBIGUOUS), Move::new(E7, E5, MoveType::UCI_AMBIGUOUS)]);
+        assert_eq!(info.pv, vec![Move::new(G1, F3, MoveType::UCI_AMBIGUOUS)]);
+
+        // The CPU number is optional.
+        assert_eq!(parse("currline e2e4").unwrap().currline, vec![Move::new(E2, E4, MoveType::UCI_AMBIGUOUS)]);
+    }
+
+    #[test]
+    fn wdl() {
+        let info = parse("depth 20 score cp 35 wdl 120 840 40 nodes 10 pv e2e4").unwrap();
+        assert_eq!(info.wdl, Some((120, 840, 40)));
+        assert_eq!(info.nodes, Some(10));
+    }
+
+    #[test]
+    fn unknown_parameters_are_skipped() {
+        let info = parse("depth 7 sbhits 3 4 score cp 10 frobnicate pv e2e4").unwrap();
+        assert_eq!(info.depth, Some(7));
+        assert_eq!(info.score, Some(UCIScore::Centipawns(10)));
+        assert_eq!(info.pv, vec![Move::new(E2, E4, MoveType::UCI_AMBIGUOUS)]);
+    }
+
+    #[test]
+    fn round_trips() {
+        for s in [
+            "",
+            "depth 1 seldepth 2 multipv 1 score cp 0 nodes 20 nps 1000 hashfull 0 tbhits 0 time 20 pv d2d4",
+            "depth 9 score mate -2 upperbound nodes 100 cpuload 950 currmove a7a8n currmovenumber 12",
+            "score cp -45 lowerbound pv e2e4 string hello there",
+            "depth 20 score cp 35 wdl 120 840 40 pv e2e4 refutation d1h5 g6h5 currline e2e4 e7e5",
+        ] {
+            let info = parse(s).unwrap();
+            assert_eq!(info.to_string(), s);
+            assert_eq!(parse(&info.to_string()), Ok(info));
+        }
+    }
+}
@@ -7,7 +7,13 @@ pub const START_POSITION_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBN
 pub const LONDON_POSITION_FEN: &str = "r1bqk2r/pp2bppp/2n1pn2/2pp4/3P1B2/2P1PN1P/PP1N1PP1/R2QKB1R b KQkq - 0 7";
 
 pub mod connection;
+pub mod go_params;
+pub mod info;
+pub mod options;
 pub use connection::run;
+pub use go_params::{GoParams, GoParamsError};
+pub use info::{UCIBound, UCIInfo, UCIInfoError, UCIScore};
+pub use options::{OptionRegistry, UCIOptionError};
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum UCIMessage {
@@ -33,7 +39,7 @@ pub enum UCIMessage {
     BestMove(String, Option<String>),
     CopyProtection,
     Registration,
-    Info(Vec<String>),
+    Info(UCIInfo),
     Option(UCIOption),
     EmptyLine,
     // Stockfish Extensions
@@ -59,8 +65,15 @@ pub struct UCIOption {
 
 impl Display for UCIOption {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        write!(f, "option name {} type {} default {} min {} max {} var {}",
-            self.name, self.option_type, self.default, self.min, self.max, self.var.join(" "))
+        // Only `name` and `type` are required, and a GUI may not like an empty value.
+        write!(f, "option name {} type {}", self.name, self.option_type)?;
+        for (keyword, value) in [("default", &self.default), ("min", &self.min), ("max", &self.max)] {
+            if !value.is_empty() { write!(f, " {} {}", keyword, value)?; }
+        }
+        for var in &self.var {
+            write!(f, " var {}", var)?;
+        }
+        Ok(())
     }
 }
 
@@ -100,7 +113,8 @@ impl UCIOption {
             "default" => self.default = value,
             "min"     => self.min = value,
             "max"     => self.max = value,
-            "var"     => self.var = value.split_whitespace().map(|s| s.to_string()).collect(),
+            // Each choice has its own `var`, but some engines put several after one.
+            "var"     => self.var.extend(value.split_whitespace().map(|s| s.to_string())),
             "option"  => { }, // ignore
             _         => { panic!("Unknown keyword: {}", keyword) }
         }
@@ -174,7 +188,7 @@ impl Display for UCIMessage {
             },
             UCIMessage::CopyProtection => write!(f, "copyprotection"),
             UCIMessage::Registration => write!(f, "registration"),
-            UCIMessage::Info(info) => write!(f, "info {}", info.join(" ")),
+            UCIMessage::Info(info) => write!(f, "info {}", info),
             UCIMessage::Option(option) => write!(f, "{}", option),
             UCIMessage::EmptyLine => write!(f, ""),
             UCIMessage::D => write!(f, "d"),
@@ -202,13 +216,14 @@ impl UCIMessage {
             Some("register") => UCIMessage::Register,
             Some("ucinewgame") => UCIMessage::UCINewGame,
             Some("setoption") => {
-                let name = parts.nth(1).unwrap().to_string();
-                match parts.next() {
-                    Some("value") => {
-                        let value = parts.next().map(|s| s.to_string());
-                        UCIMessage::SetOption(name, value)
+                // Both the name and the value can be several words, e.g. `name Clear Hash`.
+                let rest: Vec<&str> = parts.skip_while(|&s| s == "name").collect();
+                match rest.iter().position(|&s| s == "value") {
+                    Some(idx) => {
+                        let value = rest[idx + 1..].join(" ");
+                        UCIMessage::SetOption(rest[..idx].join(" "), (!value.is_empty()).then_some(value))
                     }
-                    _ => UCIMessage::SetOption(name, None)
+                    None => UCIMessage::SetOption(rest.join(" "), None)
                 }
             }
             Some("position") => {
@@ -245,12 +260,11 @@ impl UCIMessage {
             Some("copyprotection") => UCIMessage::CopyProtection,
             Some("registration") => UCIMessage::Registration,
             Some("info") => {
-                UCIMessage::Info(
-                    parts.collect::<Vec<&str>>()
-                        .chunks(2)
-                        .map(|s| s.join(" ").to_string())
-                        .collect()
-                )
+                let mut info = UCIInfo::default();
+                if let Err(err) = info.parse_into(&parts.collect::<Vec<&str>>()) {
+                    tracing::error!("{}, dropping the rest of the line", err);
+                }
+                UCIMessage::Info(info)
             }
             Some("option") => {
                 UCIMessage::Option(UCIOption::parse(message))
@@ -282,6 +296,9 @@ impl UCIMessage {
 
 #[cfg(test)]
 mod tests {
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::{Move, MoveType};
+
     use super::*;
 
     mod display {
@@ -379,14 +396,15 @@ mod tests {
 
         #[test]
         fn displays_info() {
-            assert_displays!(UCIMessage::Info(vec![
-                "depth 1".to_string(),
-                "seldepth 1".to_string(),
-                "nodes 1".to_string(),
-                "nps 1".to_string(),
-                "time 1".to_string(),
-                "pv e2e4".to_string()
-            ]), "info depth 1 seldepth 1 nodes 1 nps 1 time 1 pv e2e4");
+            assert_displays!(UCIMessage::Info(UCIInfo {
+                depth: Some(1),
+                seldepth: Some(1),
+                nodes: Some(1),
+                nps: Some(1),
+                time: Some(1),
+                pv: vec![Move::new(E2, E4, MoveType::UCI_AMBIGUOUS)],
+                ..Default::default()
+            }), "info depth 1 seldepth 1 nodes 1 nps 1 time 1 pv e2e4");
         }
 
         #[test]
@@ -398,7 +416,15 @@ mod tests {
                 "".to_string(),
                 "".to_string(),
                 vec![]
-            )), "option name NullMove type check default true min  max  var ");
+            )), "option name NullMove type check default true");
+            assert_displays!(UCIMessage::Option(UCIOption::new(
+                "Style".to_string(),
+                "combo".to_string(),
+                "Normal".to_string(),
+                "".to_string(),
+                "".to_string(),
+                vec!["Solid".to_string(), "Normal".to_string()]
+            )), "option name Style type combo default Normal var Solid var Normal");
         }
 
         #[test]
@@ -467,6 +493,14 @@ mod tests {
                 "setoption name NullMove",
                 UCIMessage::SetOption("NullMove".to_string(), None)
             );
+            assert_parses!(
+                "setoption name Clear Hash",
+                UCIMessage::SetOption("Clear Hash".to_string(), None)
+            );
+            assert_parses!(
+                "setoption name Book File value /home/me/my books/book.bin",
+                UCIMessage::SetOption("Book File".to_string(), Some("/home/me/my books/book.bin".to_string()))
+            );
         }
 
         #[test]
@@ -564,17 +598,26 @@ mod tests {
         fn parses_info() {
             assert_parses!(
                 "info depth 1 seldepth 1 nodes 1 nps 1 time 1 pv e2e4",
-                UCIMessage::Info(vec![
-                    "depth 1".to_string(),
-                    "seldepth 1".to_string(),
-                    "nodes 1".to_string(),
-                    "nps 1".to_string(),
-                    "time 1".to_string(),
-                    "pv e2e4".to_string()
-                ])
+                UCIMessage::Info(UCIInfo {
+                    depth: Some(1),
+                    seldepth: Some(1),
+                    nodes: Some(1),
+                    nps: Some(1),
+                    time: Some(1),
+                    pv: vec![Move::new(E2, E4, MoveType::UCI_AMBIGUOUS)],
+                    ..Default::default()
+                })
             );
         }
 
+        #[test]
+        fn malformed_info_keeps_what_came_before() {
+            // Unknown parameters are fine, see `UCIInfo`, but a bad value spoils the rest of the
+            // line.
+            assert_parses!("info depth deep", UCIMessage::Info(UCIInfo::default()));
+            assert_parses!("info depth 3 nodes lots pv e2e4", UCIMessage::Info(UCIInfo { depth: Some(3), ..Default::default() }));
+        }
+
         #[test]
         #[allow(non_snake_case)] // I like naming puns, especially when they're this bad.
         fn parses_CamelCase_option() {
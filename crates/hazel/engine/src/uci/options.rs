@@ -0,0 +1,207 @@
+use std::fmt::{self, Display, Formatter};
+
+use super::UCIOption;
+
+/// How a `string` option with no default is written, per the spec.
+const EMPTY: &str = "<empty>";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UCIOptionError {
+    UnknownOption(String),
+    /// Everything but a `button` needs a value.
+    MissingValue(String),
+    /// Not a number, not `true` or `false`, or not one of a combo's choices. Holds the option
+    /// and the value.
+    InvalidValue(String, String),
+}
+
+impl Display for UCIOptionError {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            UCIOptionError::UnknownOption(name) => write!(f, "setoption: no such option {:?}", name),
+            UCIOptionError::MissingValue(name) => write!(f, "setoption: {} needs a value", name),
+            UCIOptionError::InvalidValue(name, value) => write!(f, "setoption: {:?} is not a valid value for {}", value, name),
+        }
+    }
+}
+
+impl UCIOption {
+    pub fn check(name: &str, default: bool) -> Self {
+        Self::typed(name, "check", &default.to_string(), "", "", vec![])
+    }
+
+    pub fn spin(name: &str, default: i64, min: i64, max: i64) -> Self {
+        Self::typed(name, "spin", &default.to_string(), &min.to_string(), &max.to_string(), vec![])
+    }
+
+    pub fn combo(name: &str, default: &str, choices: &[&str]) -> Self {
+        Self::typed(name, "combo", default, "", "", choices.iter().map(|choice| choice.to_string()).collect())
+    }
+
+    pub fn string(name: &str, default: &str) -> Self {
+        Self::typed(name, "string", if default.is_empty() { EMPTY } else { default }, "", "", vec![])
+    }
+
+    pub fn button(name: &str) -> Self {
+        Self::typed(name, "button", "", "", "", vec![])
+    }
+
+    fn typed(name: &str, option_type: &str, default: &str, min: &str, max: &str, var: Vec<String>) -> Self {
+        Self::new(name.to_string(), option_type.to_string(), default.to_string(), min.to_string(), max.to_string(), var)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn option_type(&self) -> &str {
+        &self.option_type
+    }
+
+    /// The value the option starts with, as `coerce` would have it. Buttons don't have one.
+    pub fn default_value(&self) -> Option<String> {
+        match self.option_type.as_str() {
+            "button" => None,
+            "string" if self.default == EMPTY => Some(String::new()),
+            _ => Some(self.default.clone()),
+        }
+    }
+
+    /// Checks a value from `setoption` against the option's type, and tidies it up:
+    ///
+    /// - `check` is `true` or `false`, in any case.
+    /// - `spin` is clamped to `min..=max`.
+    /// - `combo` must be one of the choices, in any case, and becomes the choice as advertised.
+    /// - `string` can be anything, `<empty>` (or nothing) is the empty string.
+    /// - `button` ignores the value, and gives `None`.
+    pub fn coerce(&self, value: Option<&str>) -> Result<Option<String>, UCIOptionError> {
+        let invalid = |value: &str| UCIOptionError::InvalidValue(self.name.clone(), value.to_string());
+
+        let value = match (self.option_type.as_str(), value.map(str::trim)) {
+            ("button", _) => return Ok(None),
+            ("string", None) => return Ok(Some(String::new())),
+            ("string", Some(value)) => return Ok(Some(if value == EMPTY { String::new() } else { value.to_string() })),
+            (_, None) => return Err(UCIOptionError::MissingValue(self.name.clone())),
+            (_, Some(value)) => value,
+        };
+
+        match self.option_type.as_str() {
+            "check" if value.eq_ignore_ascii_case("true") => Ok(Some("true".to_string())),
+            "check" if value.eq_ignore_ascii_case("false") => Ok(Some("false".to_string())),
+            "spin" => {
+                let number: i64 = value.parse().map_err(|_| invalid(value))?;
+                let min = self.min.parse().unwrap_or(i64::MIN);
+                let max = self.max.parse().unwrap_or(i64::MAX);
+                Ok(Some(number.clamp(min, max).to_string()))
+            },
+            "combo" => self.var.iter()
+                .find(|choice| choice.eq_ignore_ascii_case(value))
+                .map(|choice| Some(choice.clone()))
+                .ok_or_else(|| invalid(value)),
+            _ => Err(invalid(value)),
+        }
+    }
+}
+
+/// The options an engine understands, in the order it advertises them.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OptionRegistry {
+    options: Vec<UCIOption>,
+}
+
+impl OptionRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, option: UCIOption) -> Self {
+        self.options.push(option);
+        self
+    }
+
+    /// Option names are case-insensitive.
+    pub fn get(&self, name: &str) -> Option<&UCIOption> {
+        self.options.iter().find(|option| option.name.eq_ignore_ascii_case(name.trim()))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &UCIOption> {
+        self.options.iter()
+    }
+
+    /// Finds the option and coerces the value, see `UCIOption::coerce`.
+    pub fn coerce(&self, name: &str, value: Option<&str>) -> Result<(&UCIOption, Option<String>), UCIOptionError> {
+        let option = self.get(name).ok_or_else(|| UCIOptionError::UnknownOption(name.to_string()))?;
+        Ok((option, option.coerce(value)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registry() -> OptionRegistry {
+        OptionRegistry::new()
+            .with(UCIOption::spin("Hash", 16, 1, 1024))
+            .with(UCIOption::check("Ponder", false))
+            .with(UCIOption::combo("Style", "Normal", &["Solid", "Normal", "Risky"]))
+            .with(UCIOption::string("Book File", ""))
+            .with(UCIOption::button("Clear Hash"))
+    }
+
+    fn coerce(name: &str, value: Option<&str>) -> Result<Option<String>, UCIOptionError> {
+        registry().coerce(name, value).map(|(_, value)| value)
+    }
+
+    #[test]
+    fn advertises_as_the_spec_says() {
+        let lines: Vec<String> = registry().iter().map(|option| option.to_string()).collect();
+        assert_eq!(lines, vec![
+            "option name Hash type spin default 16 min 1 max 1024",
+            "option name Ponder type check default false",
+            "option name Style type combo default Normal var Solid var Normal var Risky",
+            "option name Book File type string default <empty>",
+            "option name Clear Hash type button",
+        ]);
+    }
+
+    #[test]
+    fn names_are_case_insensitive() {
+        assert_eq!(registry().get("clear hash").map(|option| option.name()), Some("Clear Hash"));
+        assert_eq!(registry().get("Threads"), None);
+    }
+
+    #[test]
+    fn checks() {
+        assert_eq!(coerce("Ponder", Some("TRUE")), Ok(Some("true".to_string())));
+        assert_eq!(coerce("Ponder", Some("False")), Ok(Some("false".to_string())));
+        assert_eq!(coerce("Ponder", Some("yes")), Err(UCIOptionError::InvalidValue("Ponder".to_string(), "yes".to_string())));
+        assert_eq!(coerce("Ponder", None), Err(UCIOptionError::MissingValue("Ponder".to_string())));
+    }
+
+    #[test]
+    fn spins_are_clamped() {
+        assert_eq!(coerce("Hash", Some("64")), Ok(Some("64".to_string())));
+        assert_eq!(coerce("Hash", Some("0")), Ok(Some("1".to_string())));
+        assert_eq!(coerce("Hash", Some("99999")), Ok(Some("1024".to_string())));
+        assert_eq!(coerce("Hash", Some("lots")), Err(UCIOptionError::InvalidValue("Hash".to_string(), "lots".to_string())));
+    }
+
+    #[test]
+    fn combos_must_be_a_choice() {
+        assert_eq!(coerce("Style", Some("risky")), Ok(Some("Risky".to_string())));
+        assert_eq!(coerce("Style", Some("Wild")), Err(UCIOptionError::InvalidValue("Style".to_string(), "Wild".to_string())));
+    }
+
+    #[test]
+    fn strings_and_buttons() {
+        assert_eq!(coerce("Book File", Some("/tmp/book.bin")), Ok(Some("/tmp/book.bin".to_string())));
+        assert_eq!(coerce("Book File", Some("<empty>")), Ok(Some("".to_string())));
+        assert_eq!(coerce("Book File", None), Ok(Some("".to_string())));
+        assert_eq!(coerce("Clear Hash", Some("whatever")), Ok(None));
+    }
+
+    #[test]
+    fn unknown_options() {
+        assert_eq!(coerce("Threads", Some("4")), Err(UCIOptionError::UnknownOption("Threads".to_string())));
+    }
+}
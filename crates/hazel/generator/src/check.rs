@@ -1,4 +1,13 @@
-use hazel_representation::{coup::rep::Move, game::position::Position};
+use hazel_bitboard::bitboard::Bitboard;
+use hazel_bitboard::constants::move_tables::KING_ATTACKS;
+use hazel_core::interface::Query;
+use hazel_core::piece::Piece;
+use hazel_core::square::Square;
+use hazel_representation::coup::rep::{Move, MoveType};
+use hazel_representation::game::position::Position;
+
+use crate::mode::Targets;
+use crate::{knight, pawn, pin, slider};
 
 pub fn is_in_check(position: &Position) -> bool {
     position.their_reach().is_set(position.our_king())
@@ -10,6 +19,119 @@ pub fn is_in_check(position: &Position) -> bool {
 
 
 // Generate all valid moves which resolve the check, this is any kind move, or any intervening move
-pub fn generate_moves(_position: &Position) -> impl Iterator<Item = Move> {
-    vec![].into_iter()
+pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
+    let mut ret : Vec<Move> = king_evasions(position).collect();
+
+    let checkers = position.checkers();
+
+    // In double check, the only way out is for the king to walk.
+    if checkers.count() != 1 { return ret.into_iter(); }
+
+    let checker = checkers.into_iter().next().unwrap();
+    let evasion_mask = checkers | interpositions(position, checker);
+
+    let targets = Targets::all();
+    let blocks_or_captures = pawn::generate_moves(position, &targets).chain(
+        knight::generate_moves(position, &targets)).chain(
+        slider::bishop::generate_moves(position, &targets)).chain(
+        slider::rook::generate_moves(position, &targets)).chain(
+        slider::queen::generate_moves(position, &targets))
+        .filter(|mov| {
+            // An en passant capture lands behind the checker, rather than on it, so look at the
+            // square of the pawn it actually removes.
+            evasion_mask.is_set(mov.target()) ||
+                (mov.is_en_passant() && en_passant_victim(mov) == checker)
+        });
+
+    ret.extend(blocks_or_captures);
+    ret.into_iter()
+}
+
+/// King steps to any square they don't attack, including captures of undefended pieces.
+fn king_evasions(position: &Position) -> impl Iterator<Item = Move> {
+    let source_sq = position.our_king();
+    let safe = KING_ATTACKS[source_sq.index()] & !position.friendlies() & !position.their_king_danger();
+    let captures = safe & position.enemies();
+    let quiets = safe & !captures;
+
+    captures.into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::CAPTURE)).chain(
+    quiets.into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::QUIET)))
+}
+
+/// The squares strictly between our king and a checking slider. Empty for any other checker.
+fn interpositions(position: &Position, checker: Square) -> Bitboard {
+    match position.get(checker).piece() {
+        Some(Piece::Bishop) | Some(Piece::Rook) | Some(Piece::Queen) => pin::between(position.our_king(), checker),
+        _ => Bitboard::empty()
+    }
+}
+
+/// The square of the pawn removed by an en passant capture.
+fn en_passant_victim(mov: &Move) -> Square {
+    Square::from((mov.source().rank(), mov.target().file()))
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn evasions(fen: &str) -> Vec<Move> {
+        let position = Position::new(BEN::new(fen));
+        generate_moves(&position).sorted().collect()
+    }
+
+    #[test]
+    fn king_cannot_retreat_along_the_checking_ray() {
+        let moves = evasions("4k3/8/8/8/8/8/8/r3K3 w - - 0 1");
+        similar_asserts::assert_eq!(moves, vec![
+            Move::new(E1, D2, MoveType::QUIET),
+            Move::new(E1, E2, MoveType::QUIET),
+            Move::new(E1, F2, MoveType::QUIET),
+        ].into_iter().sorted().collect::<Vec<Move>>());
+    }
+
+    #[test]
+    fn king_cannot_capture_a_defended_checker() {
+        let moves = evasions("4k3/8/8/b7/8/8/3p4/4K3 w - - 0 1");
+        assert!(!moves.contains(&Move::new(E1, D2, MoveType::CAPTURE)));
+
+        let moves = evasions("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1");
+        assert!(moves.contains(&Move::new(E1, D2, MoveType::CAPTURE)));
+    }
+
+    #[test]
+    fn captures_and_interpositions() {
+        let moves = evasions("4k3/8/8/8/8/4N3/1R6/r3K3 w - - 0 1");
+        similar_asserts::assert_eq!(moves, vec![
+            Move::new(B2, B1, MoveType::QUIET),
+            Move::new(E1, D2, MoveType::QUIET),
+            Move::new(E1, E2, MoveType::QUIET),
+            Move::new(E1, F2, MoveType::QUIET),
+            Move::new(E3, D1, MoveType::QUIET),
+        ].into_iter().sorted().collect::<Vec<Move>>());
+    }
+
+    #[test]
+    fn capturing_the_checker() {
+        let moves = evasions("4k3/3R4/8/8/8/3n4/8/4K3 w - - 0 1");
+        assert!(moves.contains(&Move::new(D7, D3, MoveType::CAPTURE)));
+        assert!(moves.iter().all(|m| m.source() == E1 || m.target() == D3));
+    }
+
+    #[test]
+    fn en_passant_capture_of_the_checking_pawn() {
+        let moves = evasions("8/8/8/2k5/3Pp3/8/8/4K3 b - d3 0 1");
+        assert!(moves.contains(&Move::new(E4, D3, MoveType::EP_CAPTURE)));
+    }
+
+    #[test]
+    fn double_check_only_allows_king_moves() {
+        let moves = evasions("4k3/8/8/8/1b6/8/4r3/3QK3 w - - 0 1");
+        assert!(!moves.is_empty());
+        assert!(moves.iter().all(|m| m.source() == E1));
+    }
 }
@@ -1,14 +1,58 @@
+use hazel_bitboard::bitboard;
+use hazel_bitboard::bitboard::Bitboard;
+use hazel_core::color::Color;
+use hazel_core::interface::Query;
+use hazel_core::occupant::Occupant;
+use hazel_core::piece::Piece;
+use hazel_core::square::*;
 use hazel_representation::{coup::rep::{Move, MoveType}, game::position::Position};
 
-pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
-    // assumes we aren't in check, captures assume piece is not protected.
+use crate::mode::Targets;
+
+pub fn generate_moves(position: &Position, targets: &Targets) -> impl Iterator<Item = Move> {
+    // assumes we aren't in check.
     let source_sq = position.our_king();
-    let king_attacks = position.our_king_attacks() & !position.their_reach() & !position.friendlies(); // this should really check for defense of the other pieces?
-    let king_quiet = position.our_king_moves() & !position.their_reach() & !king_attacks;
+    let allowed = !position.their_king_danger() & targets.mask(Piece::King, source_sq);
+    let king_attacks = if targets.tactical() { position.our_king_attacks() & allowed } else { Bitboard::empty() };
+    let king_quiet = if targets.quiet() { position.our_king_moves() & allowed & !position.enemies() } else { Bitboard::empty() };
+    let castles : Vec<Move> = if targets.quiet() { castles(position).collect() } else { vec![] };
 
 
     king_attacks.into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::CAPTURE)).chain(
-        king_quiet.into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::QUIET)))
+        king_quiet.into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::QUIET))).chain(
+        castles)
+}
+
+/// Castling is allowed if we still have the right, there is nothing between the king and rook, and
+/// the king doesn't start in, pass through, or land in check.
+pub fn castles(position: &Position) -> impl Iterator<Item = Move> {
+    let color = position.hero();
+    let rights = position.metadata().castling;
+    let blockers = position.all_blockers();
+    let reach = position.their_reach();
+
+    // (right, rook square, must be empty, must not be attacked, move)
+    let candidates = match color {
+        Color::WHITE => [
+            (rights.white_short, H1, bitboard!(F1, G1), bitboard!(E1, F1, G1), Move::short_castle(color)),
+            (rights.white_long, A1, bitboard!(B1, C1, D1), bitboard!(E1, D1, C1), Move::long_castle(color)),
+        ],
+        Color::BLACK => [
+            (rights.black_short, H8, bitboard!(F8, G8), bitboard!(E8, F8, G8), Move::short_castle(color)),
+            (rights.black_long, A8, bitboard!(B8, C8, D8), bitboard!(E8, D8, C8), Move::long_castle(color)),
+        ],
+    };
+
+    let mut ret = vec![];
+    for (right, rook_sq, empty, safe, mov) in candidates {
+        if !right { continue; }
+        if position.get(rook_sq) != Occupant::rook(color) { continue; }
+        if (blockers & empty).is_nonempty() { continue; }
+        if (reach & safe).is_nonempty() { continue; }
+
+        ret.push(mov);
+    }
+    ret.into_iter()
 }
 
 
@@ -16,7 +60,6 @@ pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
 mod tests {
 
     use hazel_representation::coup::rep::{Move, MoveType};
-    use hazel_core::square::*;
     use hazel_core::ben::BEN;
     use super::*;
 
@@ -24,7 +67,7 @@ mod tests {
     #[tracing_test::traced_test]
     fn test_position() {
         let position = Position::new(BEN::new("3k1b2/8/8/2p1P3/3K4/2p1P3/8/8 w - - 0 1"));
-        let moves = generate_moves(&position);
+        let moves = generate_moves(&position, &Targets::all());
         similar_asserts::assert_eq!(moves.collect::<Vec<Move>>(), vec![
             Move::new(D4, C3, MoveType::CAPTURE),
             Move::new(D4, D3, MoveType::QUIET),
@@ -33,4 +76,53 @@ mod tests {
             Move::new(D4, D5, MoveType::QUIET)
         ]);
     }
+
+    mod castling {
+        use super::*;
+
+        fn castles_in(fen: &str) -> Vec<Move> {
+            castles(&Position::new(BEN::new(fen))).collect()
+        }
+
+        #[test]
+        fn both_sides() {
+            similar_asserts::assert_eq!(castles_in("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1"), vec![
+                Move::short_castle(Color::WHITE),
+                Move::long_castle(Color::WHITE),
+            ]);
+            similar_asserts::assert_eq!(castles_in("r3k2r/8/8/8/8/8/8/R3K2R b KQkq - 0 1"), vec![
+                Move::short_castle(Color::BLACK),
+                Move::long_castle(Color::BLACK),
+            ]);
+        }
+
+        #[test]
+        fn requires_the_right() {
+            assert_eq!(castles_in("r3k2r/8/8/8/8/8/8/R3K2R w Qkq - 0 1"), vec![Move::long_castle(Color::WHITE)]);
+            assert!(castles_in("r3k2r/8/8/8/8/8/8/R3K2R w kq - 0 1").is_empty());
+        }
+
+        #[test]
+        fn requires_empty_squares() {
+            // b1 needs to be empty for the long castle even though the king doesn't cross it.
+            assert_eq!(castles_in("r3k2r/8/8/8/8/8/8/RN2K2R w KQkq - 0 1"), vec![Move::short_castle(Color::WHITE)]);
+        }
+
+        #[test]
+        fn cannot_castle_out_of_through_or_into_check() {
+            // out of
+            assert!(castles_in("r3k2r/8/8/8/4r3/8/8/R3K2R w KQ - 0 1").is_empty());
+            // through
+            assert_eq!(castles_in("r3k2r/8/8/8/5r2/8/8/R3K2R w KQ - 0 1"), vec![Move::long_castle(Color::WHITE)]);
+            // into
+            assert_eq!(castles_in("r3k2r/8/8/8/2r5/8/8/R3K2R w KQ - 0 1"), vec![Move::short_castle(Color::WHITE)]);
+        }
+
+        #[test]
+        fn attacked_rook_does_not_matter() {
+            assert_eq!(castles_in("r3k3/8/8/8/8/8/8/R3K3 w Q - 0 1"), vec![Move::long_castle(Color::WHITE)]);
+            // b1 may be attacked, the king never crosses it.
+            assert_eq!(castles_in("1r2k3/8/8/8/8/8/8/R3K3 w Q - 0 1"), vec![Move::long_castle(Color::WHITE)]);
+        }
+    }
 }
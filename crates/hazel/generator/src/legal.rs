@@ -0,0 +1,207 @@
+use hazel_bitboard::constants::move_tables::KING_ATTACKS;
+use hazel_core::interface::Query;
+use hazel_core::piece::Piece;
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+use crate::mode::Targets;
+use crate::pin::{self, Pins};
+use crate::{check, king, knight, pawn, slider, MoveGenerator};
+
+/// Why `MoveGenerator::is_legal` turned a move down.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IllegalMove {
+    /// Null moves are never legal, though the UCI driver may want to handle them separately.
+    NullMove,
+    /// There is nothing on the source square.
+    EmptySource,
+    /// The piece on the source square is theirs.
+    NotOurPiece,
+    /// One of our own pieces is on the target square.
+    OwnPieceOnTarget,
+    /// The piece doesn't move like that, or something is in the way.
+    Unreachable,
+    /// A castle without the right, or through pieces, or out of, through, or into check.
+    CannotCastle,
+    /// The move would leave our king in check.
+    LeavesKingInCheck,
+}
+
+impl MoveGenerator {
+    /// Checks a single move against the position without generating every move. Ambiguous moves
+    /// (e.g., from UCI) are resolved, and for the rest the metadata is only a hint, so the move
+    /// returned is the one which would actually be played.
+    ///
+    /// NOTE: `UCI_AMBIGUOUS` can't carry a promotion piece, so ambiguous promotions come back as
+    /// queen promotions. Pass a promotion move to get anything else.
+    pub fn is_legal(&self, mov: Move, position: &Position) -> Result<Move, IllegalMove> {
+        if mov.is_null() { return Err(IllegalMove::NullMove); }
+
+        let source = mov.source();
+        let target = mov.target();
+        let occupant = position.get(source);
+
+        let Some(piece) = occupant.piece() else { return Err(IllegalMove::EmptySource); };
+        if occupant.color() != Some(position.hero()) { return Err(IllegalMove::NotOurPiece); }
+        if position.friendlies().is_set(target) { return Err(IllegalMove::OwnPieceOnTarget); }
+
+        let candidates : Vec<Move> = candidates(position, piece, &Targets::only(target))
+            .filter(|candidate| candidate.source() == source && candidate.target() == target)
+            .collect();
+
+        let castling = piece == Piece::King && source.file().abs_diff(target.file()) == 2;
+
+        let found = if mov.is_promotion() {
+            candidates.into_iter().find(|candidate| candidate.is_promotion() && candidate.promotion_piece() == mov.promotion_piece())
+        } else {
+            // Only promotions have more than one candidate, and then we take the queen.
+            candidates.into_iter().max_by_key(|candidate| candidate.is_promotion() && candidate.promotion_piece() == Piece::Queen)
+        };
+
+        let Some(found) = found else {
+            return Err(if castling {
+                IllegalMove::CannotCastle
+            } else if piece == Piece::King && KING_ATTACKS[source.index()].is_set(target) {
+                // The king generator already leaves out squares they attack.
+                IllegalMove::LeavesKingInCheck
+            } else {
+                IllegalMove::Unreachable
+            });
+        };
+
+        // King moves and castles are already checked for safety, everything else has to deal with
+        // check and pins.
+        if piece != Piece::King {
+            if check::is_in_check(position) && !check::generate_moves(position).any(|evasion| evasion == found) {
+                return Err(IllegalMove::LeavesKingInCheck);
+            }
+
+            if !Pins::for_position(position).allows(&found) {
+                return Err(IllegalMove::LeavesKingInCheck);
+            }
+
+            if found.is_en_passant() && !pin::en_passant_is_safe(position, &found) {
+                return Err(IllegalMove::LeavesKingInCheck);
+            }
+        }
+
+        Ok(found)
+    }
+}
+
+/// Pseudo-legal moves for one kind of piece, masked to the given targets.
+fn candidates(position: &Position, piece: Piece, targets: &Targets) -> Box<dyn Iterator<Item = Move>> {
+    match piece {
+        Piece::Pawn => Box::new(pawn::generate_moves(position, targets)),
+        Piece::Knight => Box::new(knight::generate_moves(position, targets)),
+        Piece::Bishop => Box::new(slider::bishop::generate_moves(position, targets)),
+        Piece::Rook => Box::new(slider::rook::generate_moves(position, targets)),
+        Piece::Queen => Box::new(slider::queen::generate_moves(position, targets)),
+        Piece::King => Box::new(king::generate_moves(position, targets)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::color::Color;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    fn is_legal(fen: &str, mov: Move) -> Result<Move, IllegalMove> {
+        MoveGenerator::new().is_legal(mov, &Position::new(BEN::new(fen)))
+    }
+
+    fn ambiguous(source: Square, target: Square) -> Move {
+        Move::new(source, target, MoveType::UCI_AMBIGUOUS)
+    }
+
+    #[test]
+    fn resolves_ambiguous_moves() {
+        assert_eq!(is_legal(KIWIPETE, ambiguous(A2, A4)), Ok(Move::new(A2, A4, MoveType::DOUBLE_PAWN)));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(E5, F7)), Ok(Move::new(E5, F7, MoveType::CAPTURE)));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(E1, G1)), Ok(Move::short_castle(Color::WHITE)));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(E1, C1)), Ok(Move::long_castle(Color::WHITE)));
+    }
+
+    #[test]
+    fn resolves_en_passant() {
+        let fen = "4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1";
+        assert_eq!(is_legal(fen, ambiguous(D5, E6)), Ok(Move::new(D5, E6, MoveType::EP_CAPTURE)));
+    }
+
+    #[test]
+    fn resolves_promotions() {
+        let fen = "3r3k/4P3/8/8/8/8/8/4K3 w - - 0 1";
+        assert_eq!(is_legal(fen, ambiguous(E7, E8)), Ok(Move::new(E7, E8, MoveType::PROMOTION_QUEEN)));
+        assert_eq!(is_legal(fen, ambiguous(E7, D8)), Ok(Move::new(E7, D8, MoveType::PROMOTION_CAPTURE_QUEEN)));
+        // The capture flag is fixed up, the promotion piece is kept.
+        assert_eq!(is_legal(fen, Move::new(E7, D8, MoveType::PROMOTION_KNIGHT)), Ok(Move::new(E7, D8, MoveType::PROMOTION_CAPTURE_KNIGHT)));
+    }
+
+    #[test]
+    fn rejects_obviously_bad_moves() {
+        assert_eq!(is_legal(KIWIPETE, Move::null()), Err(IllegalMove::NullMove));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(A3, A4)), Err(IllegalMove::EmptySource));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(A7, A6)), Err(IllegalMove::NotOurPiece));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(A1, A2)), Err(IllegalMove::OwnPieceOnTarget));
+    }
+
+    #[test]
+    fn rejects_unreachable_targets() {
+        assert_eq!(is_legal(KIWIPETE, ambiguous(A2, A5)), Err(IllegalMove::Unreachable));
+        assert_eq!(is_legal(KIWIPETE, ambiguous(E2, A7)), Err(IllegalMove::Unreachable));
+        // Blocked by the knight on f6
+        assert_eq!(is_legal(KIWIPETE, ambiguous(F3, F7)), Err(IllegalMove::Unreachable));
+        assert_eq!(is_legal(KIWIPETE, Move::new(A2, A3, MoveType::PROMOTION_QUEEN)), Err(IllegalMove::Unreachable));
+    }
+
+    #[test]
+    fn rejects_bad_castles() {
+        // The knight on b1 is in the way.
+        assert_eq!(is_legal("4k3/8/8/8/8/8/8/RN2K2R w KQ - 0 1", ambiguous(E1, C1)), Err(IllegalMove::CannotCastle));
+        // No right.
+        assert_eq!(is_legal("4k3/8/8/8/8/8/8/R3K2R w Q - 0 1", ambiguous(E1, G1)), Err(IllegalMove::CannotCastle));
+        // Through check.
+        assert_eq!(is_legal("4kr2/8/8/8/8/8/8/R3K2R w KQ - 0 1", ambiguous(E1, G1)), Err(IllegalMove::CannotCastle));
+    }
+
+    #[test]
+    fn rejects_moves_which_leave_the_king_in_check() {
+        // Pinned knight
+        assert_eq!(is_legal("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1", ambiguous(D2, F3)), Err(IllegalMove::LeavesKingInCheck));
+        // Ignoring a check
+        assert_eq!(is_legal("4k3/8/8/8/8/8/8/r3K2N w - - 0 1", ambiguous(H1, G3)), Err(IllegalMove::LeavesKingInCheck));
+        // Stepping into one
+        assert_eq!(is_legal("4k3/8/8/8/8/8/3r4/7K w - - 0 1", ambiguous(H1, H2)), Err(IllegalMove::LeavesKingInCheck));
+        // En passant off the rank
+        assert_eq!(is_legal("8/8/8/KPp4r/8/8/8/7k w - c6 0 1", ambiguous(B5, C6)), Err(IllegalMove::LeavesKingInCheck));
+    }
+
+    #[test]
+    fn agrees_with_generate_moves() {
+        let gen = MoveGenerator::new();
+        for fen in [KIWIPETE, "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1", "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1"] {
+            let position = Position::new(BEN::new(fen));
+            let legal = gen.generate_moves(&position);
+
+            for source in position.friendlies() {
+                for target in 0..64 {
+                    let target = Square::new(target);
+                    let expected = legal.iter().filter(|mov| mov.source() == source && mov.target() == target)
+                        .max_by_key(|mov| mov.is_promotion() && mov.promotion_piece() == Piece::Queen);
+
+                    assert_eq!(gen.is_legal(ambiguous(source, target), &position).ok().as_ref(), expected, "{source:?}{target:?} in {fen}");
+                }
+            }
+
+            for mov in legal {
+                assert_eq!(gen.is_legal(mov, &position), Ok(mov));
+            }
+        }
+    }
+}
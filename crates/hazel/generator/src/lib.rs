@@ -5,34 +5,113 @@ use hazel_representation::coup::rep::Move;
 mod check;
 mod king;
 mod knight;
+mod legal;
+mod mode;
+mod ordering;
 mod pawn;
+mod perft;
+mod picker;
+mod pin;
+mod see;
 mod slider;
+mod terminal;
+
+pub use legal::IllegalMove;
+pub use mode::{gives_check, GenerationMode};
+pub use ordering::{MoveOrdering, HISTORY_MAX};
+pub use perft::{PerftStats, PerftTable, DEFAULT_PERFT_TABLE_MB};
+pub use picker::{mvv_lva, MovePicker, Stage};
+pub use see::{see, see_capture, SEE_VALUES};
+pub use terminal::terminal;
+use mode::Targets;
+use pin::Pins;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Legality {
+    /// Only moves which may actually be played, nothing leaves our king in check.
+    #[default]
+    Strict,
+    /// Moves which follow the movement rules of each piece, but may leave a pinned piece's king
+    /// hanging.
+    PseudoLegal,
+}
 
 #[derive(Debug, Default)]
 pub struct MoveGenerator {
     // This should actually just be passed into the generate_moves, and MoveGen is just for holding
     // caches.
     // TODO: Cache anything worth caching?
+    legality: Legality,
 }
 
 impl MoveGenerator {
     pub fn new() -> Self {
-        Self { }
+        Self::with_legality(Legality::Strict)
+    }
+
+    pub fn pseudo_legal() -> Self {
+        Self::with_legality(Legality::PseudoLegal)
+    }
+
+    pub fn with_legality(legality: Legality) -> Self {
+        Self { legality }
+    }
+
+    pub fn legality(&self) -> Legality {
+        self.legality
     }
 
     pub fn generate_moves(&self, position: &Position) -> Vec<Move> {
-        // TODO: Determine if we are in check
+        self.generate(position, GenerationMode::All)
+    }
+
+    /// Only the moves wanted by `mode`, see `GenerationMode`.
+    pub fn generate(&self, position: &Position, mode: GenerationMode) -> Vec<Move> {
+        let moves = self.pseudo_legal_moves(position, mode);
+
+        match self.legality {
+            Legality::PseudoLegal => moves,
+            Legality::Strict => {
+                // Pins only need calculating once per position, every candidate move is checked
+                // against the same set.
+                let pins = Pins::for_position(position);
+                moves.into_iter().filter(|mov| {
+                    pins.allows(mov) && (!mov.is_en_passant() || pin::en_passant_is_safe(position, mov))
+                }).collect()
+            }
+        }
+    }
+
+    fn pseudo_legal_moves(&self, position: &Position, mode: GenerationMode) -> Vec<Move> {
         if check::is_in_check(position) {
-            return check::generate_moves(position).collect();
+            // There are few enough evasions that it's not worth teaching `check` about modes.
+            return check::generate_moves(position).filter(|mov| {
+                let tactical = mov.is_capture() || mov.is_promotion();
+                match mode {
+                    GenerationMode::All => true,
+                    GenerationMode::Tactical => tactical,
+                    GenerationMode::Quiet => !tactical,
+                    GenerationMode::Checks => gives_check(position, mov),
+                }
+            }).collect();
         }
 
+        let targets = Targets::new(position, mode);
+
         // TODO: in parallel?
-        pawn::generate_moves(position).chain(
-        knight::generate_moves(position)).chain(
-        slider::bishop::generate_moves(position)).chain(
-        slider::rook::generate_moves(position)).chain(
-        slider::queen::generate_moves(position)).chain(
-        king::generate_moves(position)).collect()
+        let mut moves : Vec<Move> = pawn::generate_moves(position, &targets).chain(
+        knight::generate_moves(position, &targets)).chain(
+        slider::bishop::generate_moves(position, &targets)).chain(
+        slider::rook::generate_moves(position, &targets)).chain(
+        slider::queen::generate_moves(position, &targets)).chain(
+        king::generate_moves(position, &targets)).collect();
+
+        // The check masks let through some moves which don't actually check, see `Targets`.
+        if mode == GenerationMode::Checks {
+            moves.retain(|mov| gives_check(position, mov));
+        }
+
+        moves
     }
 
     pub fn perft(&self, depth: usize, position: &mut Position) -> usize {
@@ -79,6 +158,7 @@ impl MoveGenerator {
 mod tests {
 
     use hazel_core::ben::BEN;
+    use hazel_core::square::*;
 
     use super::*;
 
@@ -125,12 +205,88 @@ mod tests {
         assert_no_difference!(perft_start_position(3), 8_902);
     }
 
-    #[allow(dead_code)] // off by 140 right now
-    // #[test]
+    #[test]
     fn perft_4() {
         assert_no_difference!(perft_start_position(4), 197_281);
     }
 
+    #[test]
+    fn strict_generation_keeps_pinned_pieces_on_the_pin() {
+        // The knight on d2 is pinned by the bishop on b4.
+        let position = Position::new(BEN::new("4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1"));
+
+        let strict = MoveGenerator::new().generate_moves(&position);
+        let pseudo = MoveGenerator::pseudo_legal().generate_moves(&position);
+
+        assert!(strict.iter().all(|mov| mov.source() != D2));
+        assert!(pseudo.iter().any(|mov| mov.source() == D2));
+    }
+
+    mod modes {
+        use itertools::Itertools;
+
+        use super::*;
+
+        const POSITIONS: [&str; 5] = [
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "r3k2r/Pppp1ppp/1b3nbN/nP6/BBP1P3/q4N2/Pp1P2PP/R2Q1RK1 w kq - 0 1",
+            "rnbq1k1r/pp1Pbppp/2p5/8/2B5/8/PPP1NnPP/RNBQK2R w KQ - 1 8",
+            "4k3/8/8/8/1b6/8/3N4/4K3 w - - 0 1",
+        ];
+
+        /// Every position above, and every position one move on from them, so both sides get a go
+        /// and some of them are in check.
+        fn positions() -> Vec<Position> {
+            let gen = MoveGenerator::new();
+            POSITIONS.iter().flat_map(|fen| {
+                let root = Position::new(BEN::new(fen));
+                let mut ret = vec![root.clone()];
+                for mov in gen.generate_moves(&root) {
+                    let mut child = root.clone();
+                    child.make(mov);
+                    ret.push(child);
+                }
+                ret
+            }).collect()
+        }
+
+        #[test]
+        fn tactical_and_quiet_split_all() {
+            let gen = MoveGenerator::new();
+            for position in positions() {
+                let tactical = gen.generate(&position, GenerationMode::Tactical);
+                let quiet = gen.generate(&position, GenerationMode::Quiet);
+
+                assert!(tactical.iter().all(|mov| mov.is_capture() || mov.is_promotion()));
+                assert!(quiet.iter().all(|mov| !mov.is_capture() && !mov.is_promotion()));
+
+                similar_asserts::assert_eq!(
+                    tactical.into_iter().chain(quiet).sorted().collect::<Vec<Move>>(),
+                    gen.generate_moves(&position).into_iter().sorted().collect::<Vec<Move>>()
+                );
+            }
+        }
+
+        #[test]
+        fn checks_are_exactly_the_moves_which_check() {
+            let gen = MoveGenerator::new();
+            for mut position in positions() {
+                let expected : Vec<Move> = gen.generate_moves(&position).into_iter().filter(|mov| {
+                    position.make(*mov);
+                    let checks = check::is_in_check(&position);
+                    position.unmake();
+                    checks
+                }).sorted().collect();
+
+                similar_asserts::assert_eq!(
+                    gen.generate(&position, GenerationMode::Checks).into_iter().sorted().collect::<Vec<Move>>(),
+                    expected
+                );
+            }
+        }
+    }
+
     #[test]
     fn check_mate_position_has_zero_perft_at_any_depth() {
         let count = perft_position(1, &mut Position::new(BEN::new("7k/6Q1/6K1/8/8/8/8/8 b - - 0 1")));
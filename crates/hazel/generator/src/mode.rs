@@ -0,0 +1,239 @@
+use hazel_bitboard::bitboard::Bitboard;
+use hazel_bitboard::constants::move_tables::{KING_ATTACKS, KNIGHT_MOVES};
+use hazel_bitboard::pextboard;
+use hazel_core::color::Color;
+use hazel_core::direction::Direction;
+use hazel_core::interface::Query;
+use hazel_core::piece::{Piece, PIECE_COUNT};
+use hazel_core::square::*;
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+use crate::pin;
+
+/// Which moves to generate.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GenerationMode {
+    /// Everything.
+    #[default]
+    All,
+    /// Captures, en passant, and promotions (including non-capturing ones).
+    Tactical,
+    /// Everything `Tactical` doesn't generate, castling included.
+    Quiet,
+    /// Any move which gives check.
+    Checks,
+}
+
+/// The per-piece generators mask their targets with this, so they never generate a move the mode
+/// doesn't want.
+///
+/// NOTE: In `Checks` mode, the masks are a superset. Discovered checks, en passant and castling
+/// are all let through wholesale, and `gives_check` sorts them out after.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Targets {
+    mode: GenerationMode,
+    // For each piece, the squares it would check their king from.
+    direct: [Bitboard; PIECE_COUNT],
+    // Our pieces which are the only thing between one of our sliders and their king.
+    discoverers: Bitboard,
+}
+
+impl Targets {
+    pub fn new(position: &Position, mode: GenerationMode) -> Self {
+        if mode != GenerationMode::Checks {
+            return Self { mode, direct: [Bitboard::full(); PIECE_COUNT], discoverers: Bitboard::empty() };
+        }
+
+        let king = position.their_king();
+        let blockers = position.all_blockers();
+
+        let behind = Bitboard::from(king).shift(position.villain().pawn_direction());
+        let diagonals = pextboard::attacks_for(Piece::Bishop, king, blockers);
+        let orthogonals = pextboard::attacks_for(Piece::Rook, king, blockers);
+
+        let mut direct = [Bitboard::empty(); PIECE_COUNT];
+        direct[Piece::Pawn as usize] = behind.shift(Direction::E) | behind.shift(Direction::W);
+        direct[Piece::Knight as usize] = KNIGHT_MOVES[king.index()];
+        direct[Piece::Bishop as usize] = diagonals;
+        direct[Piece::Rook as usize] = orthogonals;
+        direct[Piece::Queen as usize] = diagonals | orthogonals;
+
+        Self { mode, direct, discoverers: discoverers(position) }
+    }
+
+    /// Everything, without needing a position to work it out from.
+    pub fn all() -> Self {
+        Self { mode: GenerationMode::All, direct: [Bitboard::full(); PIECE_COUNT], discoverers: Bitboard::empty() }
+    }
+
+    /// Only moves landing on `target`, for when we're looking for one move in particular.
+    pub fn only(target: Square) -> Self {
+        Self { mode: GenerationMode::All, direct: [Bitboard::from(target); PIECE_COUNT], discoverers: Bitboard::empty() }
+    }
+
+    /// Whether to generate captures and promotions.
+    pub fn tactical(&self) -> bool {
+        self.mode != GenerationMode::Quiet
+    }
+
+    /// Whether to generate non-capturing, non-promoting moves.
+    pub fn quiet(&self) -> bool {
+        self.mode != GenerationMode::Tactical
+    }
+
+    /// The squares a `piece` on `source` may move to in this mode.
+    pub fn mask(&self, piece: Piece, source: Square) -> Bitboard {
+        if self.discoverers.is_set(source) {
+            Bitboard::full()
+        } else {
+            self.direct[piece as usize]
+        }
+    }
+
+    pub fn allows(&self, piece: Piece, mov: &Move) -> bool {
+        self.mask(piece, mov.source()).is_set(mov.target())
+    }
+}
+
+fn discoverers(position: &Position) -> Bitboard {
+    let king = position.their_king();
+    let hero = position.hero();
+    let enemies = position.enemies();
+    let friendlies = position.friendlies();
+    let queens = position.queens_for(&hero);
+
+    // Same trick as finding pins, but from their king and looking through our pieces.
+    let sliders =
+        (pextboard::attacks_for(Piece::Bishop, king, enemies) & (position.bishops_for(&hero) | queens)) |
+        (pextboard::attacks_for(Piece::Rook, king, enemies) & (position.rooks_for(&hero) | queens));
+
+    let mut ret = Bitboard::empty();
+    for slider in sliders {
+        let blockers = pin::between(king, slider) & position.all_blockers();
+        if blockers.count() == 1 && (blockers & friendlies).is_nonempty() {
+            ret |= blockers;
+        }
+    }
+    ret
+}
+
+/// Whether `mov` puts their king in check, directly or by discovery.
+pub fn gives_check(position: &Position, mov: &Move) -> bool {
+    let hero = position.hero();
+    let king = position.their_king();
+    let source = mov.source();
+
+    let mut blockers = position.all_blockers();
+    blockers.unset(source);
+    blockers.set(mov.target());
+
+    // Everything which moved, since their old squares are where `attackers_of` will look for them.
+    let mut moved = Bitboard::from(source);
+
+    // The piece (and where it ends up) which might now be checking directly.
+    let (piece, landing) = if mov.is_short_castle() || mov.is_long_castle() {
+        let (rook_from, rook_to) = match (mov.is_short_castle(), hero) {
+            (true, Color::WHITE) => (H1, F1),
+            (false, Color::WHITE) => (A1, D1),
+            (true, Color::BLACK) => (H8, F8),
+            (false, Color::BLACK) => (A8, D8),
+        };
+        blockers.unset(rook_from);
+        blockers.set(rook_to);
+        moved.set(rook_from);
+        (Piece::Rook, rook_to)
+    } else if mov.is_promotion() {
+        (mov.promotion_piece(), mov.target())
+    } else {
+        (position.get(source).piece().unwrap(), mov.target())
+    };
+
+    if mov.is_en_passant() {
+        blockers.unset(Square::from((source.rank(), mov.target().file())));
+    }
+
+    let direct = match piece {
+        Piece::Pawn => {
+            let ahead = Bitboard::from(landing).shift(hero.pawn_direction());
+            ahead.shift(Direction::E) | ahead.shift(Direction::W)
+        }
+        Piece::Knight => KNIGHT_MOVES[landing.index()],
+        Piece::King => KING_ATTACKS[landing.index()],
+        slider => pextboard::attacks_for(slider, landing, blockers),
+    };
+
+    if direct.is_set(king) { return true; }
+
+    // Anything else of ours now attacking the king was uncovered by the move.
+    (position.attackers_of(king, &hero, blockers) & !moved).is_nonempty()
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_bitboard::bitboard;
+    use hazel_core::ben::BEN;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    #[test]
+    fn direct_check_squares() {
+        let position = Position::new(BEN::new("4k3/8/8/8/8/8/8/4K3 w - - 0 1"));
+        let targets = Targets::new(&position, GenerationMode::Checks);
+
+        assert_eq!(targets.mask(Piece::Pawn, A1), bitboard!(D7, F7));
+        assert_eq!(targets.mask(Piece::Knight, A1), bitboard!(C7, D6, F6, G7));
+        assert_eq!(targets.mask(Piece::King, A1), Bitboard::empty());
+    }
+
+    #[test]
+    fn discoverers_may_go_anywhere() {
+        let position = Position::new(BEN::new("4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1"));
+        let targets = Targets::new(&position, GenerationMode::Checks);
+
+        assert_eq!(targets.mask(Piece::Knight, E4), Bitboard::full());
+    }
+
+    #[test]
+    fn non_check_modes_mask_nothing() {
+        let position = Position::new(BEN::start_position());
+        let targets = Targets::new(&position, GenerationMode::Tactical);
+
+        assert_eq!(targets.mask(Piece::Knight, B1), Bitboard::full());
+        assert!(targets.tactical());
+        assert!(!targets.quiet());
+    }
+
+    #[test]
+    fn gives_check_directly() {
+        let position = Position::new(BEN::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"));
+        assert!(gives_check(&position, &Move::new(A1, A8, MoveType::QUIET)));
+        assert!(!gives_check(&position, &Move::new(A1, A7, MoveType::QUIET)));
+    }
+
+    #[test]
+    fn gives_check_by_discovery() {
+        let position = Position::new(BEN::new("4k3/8/8/8/4N3/8/8/4RK2 w - - 0 1"));
+        assert!(gives_check(&position, &Move::new(E4, C5, MoveType::QUIET)));
+    }
+
+    #[test]
+    fn gives_check_by_castling() {
+        let position = Position::new(BEN::new("5k2/8/8/8/8/8/8/4K2R w K - 0 1"));
+        assert!(gives_check(&position, &Move::short_castle(Color::WHITE)));
+    }
+
+    #[test]
+    fn gives_check_by_promotion() {
+        let position = Position::new(BEN::new("7k/4P3/8/8/8/8/8/4K3 w - - 0 1"));
+        assert!(gives_check(&position, &Move::new(E7, E8, MoveType::PROMOTION_QUEEN)));
+        assert!(!gives_check(&position, &Move::new(E7, E8, MoveType::PROMOTION_KNIGHT)));
+    }
+
+    #[test]
+    fn gives_check_by_en_passant_discovery() {
+        let position = Position::new(BEN::new("8/8/8/RPp4k/8/8/8/4K3 w - c6 0 1"));
+        assert!(gives_check(&position, &Move::new(B5, C6, MoveType::EP_CAPTURE)));
+    }
+}
@@ -0,0 +1,219 @@
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+use crate::picker::mvv_lva;
+
+/// Histories are kept within +/- this, so old successes fade as new ones come in.
+pub const HISTORY_MAX: i32 = 16_384;
+
+// Where each kind of move sorts, best first. Quiets sort by history alone, so they stay below
+// everything else.
+const HASH_MOVE: i32 = i32::MAX;
+const TACTICAL: i32 = 1_000_000;
+const KILLER: i32 = 900_000;
+const COUNTERMOVE: i32 = 800_000;
+
+/// What a search learns about which moves are good, to try them earlier next time.
+///
+/// - Killers: the last two quiet moves to cause a cutoff at each ply.
+/// - History: for each source and target square, how often a quiet move between them has caused
+///   a cutoff (and how often it didn't), weighted towards deeper searches.
+/// - Countermoves: for each source and target of the previous move, the quiet move which last
+///   refuted it.
+///
+/// NOTE: None of this looks at which piece is moving, a knight on e4 and a bishop on e4 share
+/// entries.
+#[derive(Debug, Clone)]
+pub struct MoveOrdering {
+    killers: Vec<[Option<Move>; 2]>,
+    history: Box<[[i32; 64]; 64]>,
+    countermoves: Box<[[Option<Move>; 64]; 64]>,
+}
+
+impl Default for MoveOrdering {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MoveOrdering {
+    pub fn new() -> Self {
+        Self {
+            killers: vec![],
+            history: Box::new([[0; 64]; 64]),
+            countermoves: Box::new([[None; 64]; 64]),
+        }
+    }
+
+    /// Forget everything, e.g. for a new game.
+    pub fn clear(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Between searches of the same game, killers are out of date (the plies have shifted), but
+    /// history is still worth something, so it's only halved.
+    pub fn new_search(&mut self) {
+        self.killers.clear();
+        for row in self.history.iter_mut() {
+            for entry in row.iter_mut() { *entry /= 2; }
+        }
+    }
+
+    pub fn killers(&self, ply: usize) -> Vec<Move> {
+        self.killers.get(ply).map_or(vec![], |killers| killers.iter().flatten().copied().collect())
+    }
+
+    pub fn is_killer(&self, ply: usize, mov: Move) -> bool {
+        self.killers.get(ply).is_some_and(|killers| killers.contains(&Some(mov)))
+    }
+
+    pub fn store_killer(&mut self, ply: usize, mov: Move) {
+        if self.killers.len() <= ply {
+            self.killers.resize(ply + 1, [None; 2]);
+        }
+
+        let killers = &mut self.killers[ply];
+        if killers[0] != Some(mov) {
+            killers[1] = killers[0];
+            killers[0] = Some(mov);
+        }
+    }
+
+    pub fn history(&self, mov: Move) -> i32 {
+        self.history[mov.source().index()][mov.target().index()]
+    }
+
+    /// Rewards (or, with a negative bonus, punishes) a quiet move. The closer the entry already is
+    /// to the limit, the less it moves.
+    pub fn update_history(&mut self, mov: Move, bonus: i32) {
+        let bonus = bonus.clamp(-HISTORY_MAX, HISTORY_MAX);
+        let entry = &mut self.history[mov.source().index()][mov.target().index()];
+        *entry += bonus - *entry * bonus.abs() / HISTORY_MAX;
+    }
+
+    pub fn countermove(&self, previous: Move) -> Option<Move> {
+        self.countermoves[previous.source().index()][previous.target().index()]
+    }
+
+    pub fn store_countermove(&mut self, previous: Move, mov: Move) {
+        self.countermoves[previous.source().index()][previous.target().index()] = Some(mov);
+    }
+
+    /// Everything we'd want to try before the rest of the quiets at this node, killers first.
+    pub fn quiet_candidates(&self, ply: usize, previous: Option<Move>) -> Vec<Move> {
+        let mut ret = self.killers(ply);
+        if let Some(counter) = previous.and_then(|previous| self.countermove(previous)) {
+            if !ret.contains(&counter) { ret.push(counter); }
+        }
+        ret
+    }
+
+    /// How early to try `mov`, higher is earlier.
+    pub fn score(&self, position: &Position, mov: Move, hash_move: Option<Move>, ply: usize, previous: Option<Move>) -> i32 {
+        if Some(mov) == hash_move { return HASH_MOVE; }
+
+        if mov.is_capture() || mov.is_promotion() {
+            return TACTICAL + mvv_lva(position, &mov);
+        }
+
+        if let Some(idx) = self.killers.get(ply).and_then(|killers| killers.iter().position(|killer| *killer == Some(mov))) {
+            return KILLER - idx as i32;
+        }
+
+        if previous.is_some_and(|previous| self.countermove(previous) == Some(mov)) {
+            return COUNTERMOVE;
+        }
+
+        self.history(mov)
+    }
+
+    /// Sorts the generator's moves, best guesses first: the hash move, captures and promotions by
+    /// MVV-LVA, killers, the countermove, then everything else by history.
+    pub fn sort(&self, position: &Position, moves: &mut [Move], hash_move: Option<Move>, ply: usize, previous: Option<Move>) {
+        moves.sort_by_cached_key(|mov| std::cmp::Reverse(self.score(position, *mov, hash_move, ply, previous)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+    use crate::MoveGenerator;
+
+    #[test]
+    fn killers_keep_the_last_two() {
+        let mut ordering = MoveOrdering::new();
+        let a = Move::new(A2, A3, MoveType::QUIET);
+        let b = Move::new(B2, B3, MoveType::QUIET);
+        let c = Move::new(C2, C3, MoveType::QUIET);
+
+        assert!(ordering.killers(3).is_empty());
+        ordering.store_killer(3, a);
+        ordering.store_killer(3, a);
+        assert_eq!(ordering.killers(3), vec![a]);
+
+        ordering.store_killer(3, b);
+        ordering.store_killer(3, c);
+        assert_eq!(ordering.killers(3), vec![c, b]);
+        assert!(!ordering.is_killer(3, a));
+        assert!(ordering.killers(2).is_empty());
+
+        ordering.new_search();
+        assert!(ordering.killers(3).is_empty());
+    }
+
+    #[test]
+    fn history_is_bounded() {
+        let mut ordering = MoveOrdering::new();
+        let mov = Move::new(G1, F3, MoveType::QUIET);
+
+        for _ in 0..1000 { ordering.update_history(mov, 400); }
+        assert!(ordering.history(mov) <= HISTORY_MAX);
+        assert!(ordering.history(mov) > HISTORY_MAX / 2);
+
+        for _ in 0..1000 { ordering.update_history(mov, -400); }
+        assert!(ordering.history(mov) >= -HISTORY_MAX);
+        assert!(ordering.history(mov) < 0);
+    }
+
+    #[test]
+    fn countermoves() {
+        let mut ordering = MoveOrdering::new();
+        let previous = Move::new(E7, E5, MoveType::DOUBLE_PAWN);
+        let counter = Move::new(G1, F3, MoveType::QUIET);
+
+        assert_eq!(ordering.countermove(previous), None);
+        ordering.store_countermove(previous, counter);
+        assert_eq!(ordering.countermove(previous), Some(counter));
+        assert_eq!(ordering.quiet_candidates(0, Some(previous)), vec![counter]);
+    }
+
+    #[test]
+    fn sorts_generated_moves() {
+        let position = Position::new(BEN::new("4k3/8/8/2q1n3/3P4/8/8/R3K2R w KQ - 0 1"));
+        let mut moves = MoveGenerator::new().generate_moves(&position);
+
+        let hash = Move::new(A1, A8, MoveType::QUIET);
+        let killer = Move::new(H1, H5, MoveType::QUIET);
+        let counter = Move::short_castle(hazel_core::color::Color::WHITE);
+        let previous = Move::new(E7, E5, MoveType::QUIET);
+        let good_history = Move::new(A1, A2, MoveType::QUIET);
+
+        let mut ordering = MoveOrdering::new();
+        ordering.store_killer(1, killer);
+        ordering.store_countermove(previous, counter);
+        ordering.update_history(good_history, 100);
+
+        ordering.sort(&position, &mut moves, Some(hash), 1, Some(previous));
+
+        assert_eq!(moves[0], hash);
+        assert_eq!(moves[1], Move::new(D4, C5, MoveType::CAPTURE));
+        assert_eq!(moves[2], Move::new(D4, E5, MoveType::CAPTURE));
+        assert_eq!(moves[3], killer);
+        assert_eq!(moves[4], counter);
+        assert_eq!(moves[5], good_history);
+    }
+}
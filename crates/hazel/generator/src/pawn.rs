@@ -9,6 +9,8 @@ use hazel_core::piece::Piece;
 use hazel_core::square::Square;
 use hazel_bitboard::ColorMasks;
 
+use crate::mode::Targets;
+
 
 /// Finds all double-pawn pushes.
 pub fn double_pawn_moves(position: &Position) -> impl Iterator<Item = Move> {
@@ -126,13 +128,28 @@ pub fn promotion_captures(position: &Position) -> impl Iterator<Item = Move> {
     }))
 }
 
-pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
-    double_pawn_moves(position).chain(
-    quiet_pawn_moves(position)).chain(
-    pawn_attacks(position)).chain(
-    promotions(position)).chain(
-    promotion_captures(position)).chain(
-    en_passant(position))
+pub fn generate_moves(position: &Position, targets: &Targets) -> impl Iterator<Item = Move> {
+    let mut ret = vec![];
+
+    if targets.quiet() {
+        ret.extend(double_pawn_moves(position).chain(quiet_pawn_moves(position)));
+    }
+
+    if targets.tactical() {
+        ret.extend(pawn_attacks(position));
+    }
+
+    ret.retain(|mov| targets.allows(Piece::Pawn, mov));
+
+    // Promotions and en passant can check in ways the mask doesn't know about, but there are few
+    // enough of them that they can all go through.
+    if targets.tactical() {
+        ret.extend(promotions(position).chain(
+        promotion_captures(position)).chain(
+        en_passant(position)));
+    }
+
+    ret.into_iter()
 }
 
 
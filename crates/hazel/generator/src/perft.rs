@@ -0,0 +1,337 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::sync::RwLock;
+use std::thread;
+
+use hazel_core::zobrist::Zobrist;
+
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::chess::position::Position;
+
+use crate::{check, MoveGenerator};
+
+/// Counts of the interesting kinds of move found at a single depth of a perft run, these match
+/// the columns of the tables on https://www.chessprogramming.org/Perft_Results
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PerftStats {
+    pub nodes: usize,
+    pub captures: usize,
+    pub en_passants: usize,
+    pub castles: usize,
+    pub promotions: usize,
+    pub checks: usize,
+    pub checkmates: usize,
+}
+
+impl PerftStats {
+    fn record(&mut self, mov: &Move) {
+        self.nodes += 1;
+        if mov.is_capture() { self.captures += 1; }
+        if mov.is_en_passant() { self.en_passants += 1; }
+        if mov.is_short_castle() || mov.is_long_castle() { self.castles += 1; }
+        if mov.is_promotion() { self.promotions += 1; }
+    }
+}
+
+/// Size of a `PerftTable`, in megabytes, if you don't have a better idea.
+pub const DEFAULT_PERFT_TABLE_MB: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Entry {
+    key: Zobrist,
+    depth: u8,
+    count: usize,
+}
+
+/// Two entries per bucket, like the transposition table. The first only gets replaced by a count
+/// at least as deep, since those save the most work, the second is replaced every time the first
+/// isn't.
+type Bucket = [Option<Entry>; 2];
+
+const DEPTH_PREFERRED: usize = 0;
+const ALWAYS_REPLACE: usize = 1;
+
+/// Perft counts keyed by (Zobrist, remaining depth), shared between threads. A transposition
+/// reached at the same depth has the same count, so deep perfts skip most of the tree.
+///
+/// The table is a fixed size, indexed by the low bits of the zobrist, so a long sweep can't eat all
+/// the memory, it just starts forgetting the shallower counts.
+///
+/// NOTE: A zobrist collision here will quietly give a wrong count. That's an acceptable risk for
+/// sweeps, but if a count is off, rerun without the table before blaming movegen.
+pub struct PerftTable {
+    buckets: RwLock<Vec<Bucket>>,
+}
+
+impl Default for PerftTable {
+    fn default() -> Self {
+        Self::new(DEFAULT_PERFT_TABLE_MB)
+    }
+}
+
+// The table itself is far too big to print.
+impl Debug for PerftTable {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "PerftTable({} buckets, {} counts)", self.capacity(), self.size())
+    }
+}
+
+impl PerftTable {
+    pub fn new(megabytes: usize) -> Self {
+        Self { buckets: RwLock::new(vec![[None; 2]; Self::buckets_for(megabytes)]) }
+    }
+
+    /// The largest power of two number of buckets which fits, but always at least one.
+    fn buckets_for(megabytes: usize) -> usize {
+        let count = (megabytes * 1024 * 1024) / std::mem::size_of::<Bucket>();
+        if count == 0 { 1 } else { 1 << count.ilog2() }
+    }
+
+    /// Number of buckets.
+    pub fn capacity(&self) -> usize {
+        self.buckets.read().unwrap().len()
+    }
+
+    fn index(key: Zobrist, len: usize) -> usize {
+        (key.inner() as usize) & (len - 1)
+    }
+
+    pub fn get(&self, position: &Position, depth: usize) -> Option<usize> {
+        let key = position.zobrist().position;
+        let buckets = self.buckets.read().unwrap();
+        buckets[Self::index(key, buckets.len())].iter().flatten()
+            .find(|entry| entry.key == key && entry.depth as usize == depth)
+            .map(|entry| entry.count)
+    }
+
+    pub fn set(&self, position: &Position, depth: usize, count: usize) {
+        // Deeper than this and perft would never finish anyway.
+        let Ok(depth) = u8::try_from(depth) else { return; };
+        let entry = Entry { key: position.zobrist().position, depth, count };
+
+        let mut buckets = self.buckets.write().unwrap();
+        let len = buckets.len();
+        let bucket = &mut buckets[Self::index(entry.key, len)];
+
+        let replace_deep = match bucket[DEPTH_PREFERRED] {
+            None => true,
+            Some(old) => entry.depth >= old.depth,
+        };
+
+        if replace_deep {
+            // The old deep count is still worth keeping around, unless it's this one.
+            if let Some(old) = bucket[DEPTH_PREFERRED] {
+                if (old.key, old.depth) != (entry.key, entry.depth) { bucket[ALWAYS_REPLACE] = Some(old); }
+            }
+            bucket[DEPTH_PREFERRED] = Some(entry);
+        } else {
+            bucket[ALWAYS_REPLACE] = Some(entry);
+        }
+    }
+
+    /// Number of stored counts.
+    pub fn size(&self) -> usize {
+        self.buckets.read().unwrap().iter().flatten().flatten().count()
+    }
+}
+
+impl MoveGenerator {
+    /// Perft, but split across `threads` workers by root move. Each worker gets its own clone of
+    /// the position. If a table is provided, it is shared by all the workers.
+    pub fn parallel_perft(&self, depth: usize, position: &Position, threads: usize, table: Option<&PerftTable>) -> usize {
+        if depth == 0 { return 1; }
+
+        let moves = self.generate_moves(position);
+        let threads = threads.clamp(1, moves.len().max(1));
+
+        thread::scope(|scope| {
+            let workers : Vec<_> = (0..threads).map(|worker| {
+                // Deal the root moves out round-robin, so no worker gets all the busy ones.
+                let share : Vec<Move> = moves.iter().skip(worker).step_by(threads).copied().collect();
+                let mut position = position.clone();
+
+                scope.spawn(move || {
+                    share.into_iter().map(|mov| {
+                        position.make(mov);
+                        let count = match table {
+                            Some(table) => self.perft_with_table(depth - 1, &mut position, table),
+                            None => self.perft(depth - 1, &mut position),
+                        };
+                        position.unmake();
+                        count
+                    }).sum::<usize>()
+                })
+            }).collect();
+
+            workers.into_iter().map(|worker| worker.join().unwrap()).sum()
+        })
+    }
+
+    /// Perft, looking up and storing subtree counts in `table`.
+    pub fn perft_with_table(&self, depth: usize, position: &mut Position, table: &PerftTable) -> usize {
+        if depth == 0 { return 1; }
+
+        let moves = self.generate_moves(position);
+        // Leaf counts are just the length of the movelist, not worth a lookup.
+        if depth == 1 { return moves.len(); }
+
+        if let Some(count) = table.get(position, depth) { return count; }
+
+        let mut count = 0;
+        for mov in moves {
+            position.make(mov);
+            count += self.perft_with_table(depth - 1, position, table);
+            position.unmake();
+        }
+
+        table.set(position, depth, count);
+        count
+    }
+
+    /// The perft count under each root move, keyed by the UCI string of the move. This is the
+    /// usual way to find a movegen bug, compare against another engine and recurse into whichever
+    /// move disagrees.
+    pub fn divide(&self, depth: usize, position: &mut Position) -> BTreeMap<String, usize> {
+        let mut ret = BTreeMap::new();
+        if depth == 0 { return ret; }
+
+        for mov in self.generate_moves(position) {
+            position.make(mov);
+            ret.insert(mov.to_uci(), self.perft(depth - 1, position));
+            position.unmake();
+        }
+
+        ret
+    }
+
+    /// Like perft, but breaks down every depth from 1 to `depth`. `ret[0]` is depth 1.
+    ///
+    /// NOTE: Checkmate detection needs a full movegen at every checking leaf, so this is a good
+    /// bit slower than `perft`.
+    pub fn perft_stats(&self, depth: usize, position: &mut Position) -> Vec<PerftStats> {
+        let mut ret = vec![PerftStats::default(); depth];
+        self.collect_stats(0, position, &mut ret);
+        ret
+    }
+
+    fn collect_stats(&self, ply: usize, position: &mut Position, stats: &mut [PerftStats]) {
+        if ply == stats.len() { return; }
+
+        for mov in self.generate_moves(position) {
+            stats[ply].record(&mov);
+
+            position.make(mov);
+
+            if check::is_in_check(position) {
+                stats[ply].checks += 1;
+                if self.generate_moves(position).is_empty() {
+                    stats[ply].checkmates += 1;
+                }
+            }
+
+            self.collect_stats(ply + 1, position, stats);
+
+            position.unmake();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+
+    use super::*;
+
+    const KIWIPETE: &str = "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1";
+
+    #[test]
+    fn divide_sums_to_perft() {
+        let gen = MoveGenerator::new();
+        let mut position = Position::new(BEN::start_position());
+        let divide = gen.divide(2, &mut position);
+
+        assert_eq!(divide.len(), 20);
+        assert_eq!(divide["e2e4"], 20);
+        assert_eq!(divide.values().sum::<usize>(), gen.perft(2, &mut position));
+    }
+
+    #[test]
+    fn parallel_perft_matches_perft() {
+        let gen = MoveGenerator::new();
+        let position = Position::new(BEN::new(KIWIPETE));
+
+        assert_eq!(gen.parallel_perft(2, &position, 4, None), 2039);
+        // More threads than root moves is fine, they just don't get used.
+        assert_eq!(gen.parallel_perft(1, &position, 64, None), 48);
+    }
+
+    #[test]
+    fn perft_with_table() {
+        let gen = MoveGenerator::new();
+        let table = PerftTable::new(1);
+
+        assert_eq!(gen.perft_with_table(3, &mut Position::new(BEN::start_position()), &table), 8902);
+        assert!(table.size() > 0);
+        // Second time around the root is in the table.
+        assert_eq!(gen.perft_with_table(3, &mut Position::new(BEN::start_position()), &table), 8902);
+    }
+
+    #[test]
+    fn a_full_table_still_counts_right() {
+        let gen = MoveGenerator::new();
+        // Too small for even one bucket's worth of megabytes, so it gets just the one.
+        let table = PerftTable::new(0);
+        assert_eq!(table.capacity(), 1);
+
+        assert_eq!(gen.perft_with_table(3, &mut Position::new(BEN::new(KIWIPETE)), &table), 97862);
+        assert!(table.size() <= 2);
+        assert_eq!(gen.perft_with_table(3, &mut Position::new(BEN::new(KIWIPETE)), &table), 97862);
+    }
+
+    #[test]
+    fn parallel_perft_with_table() {
+        let gen = MoveGenerator::new();
+        let table = PerftTable::new(1);
+
+        assert_eq!(gen.parallel_perft(3, &Position::new(BEN::new(KIWIPETE)), 4, Some(&table)), 97862);
+    }
+
+    #[test]
+    fn divide_at_depth_zero_is_empty() {
+        let gen = MoveGenerator::new();
+        assert!(gen.divide(0, &mut Position::new(BEN::start_position())).is_empty());
+    }
+
+    #[test]
+    fn start_position_stats() {
+        let gen = MoveGenerator::new();
+        let stats = gen.perft_stats(3, &mut Position::new(BEN::start_position()));
+
+        similar_asserts::assert_eq!(stats, vec![
+            PerftStats { nodes: 20, ..Default::default() },
+            PerftStats { nodes: 400, ..Default::default() },
+            PerftStats { nodes: 8902, captures: 34, checks: 12, ..Default::default() },
+        ]);
+    }
+
+    #[test]
+    fn kiwipete_stats() {
+        let gen = MoveGenerator::new();
+        let stats = gen.perft_stats(2, &mut Position::new(BEN::new(KIWIPETE)));
+
+        similar_asserts::assert_eq!(stats, vec![
+            PerftStats { nodes: 48, captures: 8, castles: 2, ..Default::default() },
+            PerftStats { nodes: 2039, captures: 351, en_passants: 1, castles: 91, checks: 3, ..Default::default() },
+        ]);
+    }
+
+    #[test]
+    fn checkmates_are_counted() {
+        // Scholar's mate, one move from delivery.
+        let gen = MoveGenerator::new();
+        let mut position = Position::new(BEN::new("r1bqkbnr/pppp1ppp/2n5/4p3/2B1P3/5Q2/PPPP1PPP/RNB1K1NR w KQkq - 4 4"));
+        let stats = gen.perft_stats(1, &mut position);
+
+        assert_eq!(stats[0].checkmates, 1);
+    }
+}
@@ -0,0 +1,299 @@
+use hazel_core::interface::Query;
+use hazel_core::piece::Piece;
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+use crate::{GenerationMode, MoveGenerator, MoveOrdering};
+
+/// Where a `MovePicker` is up to. Moves come out in this order, and the searcher can ask which stage
+/// a move came from (e.g., to only update history on quiets).
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Stage {
+    #[default]
+    HashMove,
+    Captures,
+    Killers,
+    Quiets,
+    Done,
+}
+
+/// Hands out moves one at a time, best guesses first, so a search which cuts off early doesn't pay
+/// to generate or sort everything.
+///
+/// 1. The hash move, if it's legal here.
+/// 2. Captures and promotions, most valuable victim / least valuable attacker first.
+/// 3. Killer moves, if they're legal quiet moves here.
+/// 4. Everything else, in generation order, or by history if given a `MoveOrdering`.
+///
+/// NOTE: The picker doesn't hold onto the position, since the searcher needs to make/unmake on it
+/// between picks. It's on the caller to hand back the same position each time.
+#[derive(Debug, Default)]
+pub struct MovePicker {
+    stage: Stage,
+    hash_move: Option<Move>,
+    hash_tried: bool,
+    killers: Vec<Move>,
+    // Generated lazily, the first time a stage needs them. Both are kept in reverse order, so the
+    // next move to hand out is at the end.
+    captures: Option<Vec<Move>>,
+    quiets: Option<Vec<Move>>,
+}
+
+impl MovePicker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_hash_move(mut self, hash_move: Option<Move>) -> Self {
+        self.hash_move = hash_move;
+        self
+    }
+
+    pub fn with_killers(mut self, killers: &[Move]) -> Self {
+        self.killers = killers.to_vec();
+        // The killers are tried in the order given, and we pop from the end.
+        self.killers.reverse();
+        self
+    }
+
+    /// The stage of the last move handed out.
+    pub fn stage(&self) -> Stage {
+        self.stage
+    }
+
+    pub fn next_move(&mut self, generator: &MoveGenerator, position: &Position) -> Option<Move> {
+        self.next(generator, position, None)
+    }
+
+    /// As `next_move`, but the quiets are sorted by `ordering`'s history when they're generated.
+    pub fn next_ordered_move(&mut self, generator: &MoveGenerator, position: &Position, ordering: &MoveOrdering) -> Option<Move> {
+        self.next(generator, position, Some(ordering))
+    }
+
+    fn next(&mut self, generator: &MoveGenerator, position: &Position, ordering: Option<&MoveOrdering>) -> Option<Move> {
+        loop {
+            match self.stage {
+                Stage::HashMove => {
+                    if !self.hash_tried {
+                        self.hash_tried = true;
+                        if let Some(mov) = self.hash_move {
+                            // A hash collision can hand us any old move, so check it first.
+                            if generator.is_legal(mov, position) == Ok(mov) {
+                                return Some(mov);
+                            }
+                            self.hash_move = None;
+                        }
+                    }
+                    self.stage = Stage::Captures;
+                }
+                Stage::Captures => {
+                    self.generate_captures(generator, position);
+                    if let Some(mov) = self.captures.as_mut().and_then(|captures| captures.pop()) {
+                        if Some(mov) == self.hash_move { continue; }
+                        return Some(mov);
+                    }
+                    self.stage = Stage::Killers;
+                }
+                Stage::Killers => {
+                    self.generate_quiets(generator, position, ordering);
+                    match self.killers.pop() {
+                        Some(mov) => {
+                            // A killer is only any use if it's a legal quiet here, and once it's
+                            // played it shouldn't come round again with the rest of the quiets.
+                            if Some(mov) == self.hash_move { continue; }
+                            let quiets = self.quiets.as_mut().unwrap();
+                            if let Some(idx) = quiets.iter().position(|quiet| *quiet == mov) {
+                                quiets.remove(idx);
+                                return Some(mov);
+                            }
+                        }
+                        None => { self.stage = Stage::Quiets; }
+                    }
+                }
+                Stage::Quiets => {
+                    if let Some(mov) = self.quiets.as_mut().and_then(|quiets| quiets.pop()) {
+                        if Some(mov) == self.hash_move { continue; }
+                        return Some(mov);
+                    }
+                    self.stage = Stage::Done;
+                }
+                Stage::Done => { return None; }
+            }
+        }
+    }
+
+    /// For when the position isn't going to change while we walk the moves.
+    pub fn iter<'a>(&'a mut self, generator: &'a MoveGenerator, position: &'a Position) -> impl Iterator<Item = Move> + 'a {
+        std::iter::from_fn(move || self.next_move(generator, position))
+    }
+
+    fn generate_captures(&mut self, generator: &MoveGenerator, position: &Position) {
+        if self.captures.is_some() { return; }
+
+        let mut captures = generator.generate(position, GenerationMode::Tactical);
+        // Lowest score first, since we pop from the end.
+        captures.sort_by_key(|mov| mvv_lva(position, mov));
+        self.captures = Some(captures);
+    }
+
+    fn generate_quiets(&mut self, generator: &MoveGenerator, position: &Position, ordering: Option<&MoveOrdering>) {
+        if self.quiets.is_some() { return; }
+
+        let mut quiets = generator.generate(position, GenerationMode::Quiet);
+        quiets.reverse();
+        if let Some(ordering) = ordering {
+            // Lowest history first, since we pop from the end. The sort is stable, so ties still
+            // come out in generation order.
+            quiets.sort_by_key(|mov| ordering.history(*mov));
+        }
+        self.quiets = Some(quiets);
+    }
+}
+
+/// Rough ordinal value of a piece, only good for ordering.
+fn ordinal(piece: Piece) -> i32 {
+    match piece {
+        Piece::Pawn => 1,
+        Piece::Knight => 2,
+        Piece::Bishop => 3,
+        Piece::Rook => 4,
+        Piece::Queen => 5,
+        Piece::King => 6,
+    }
+}
+
+/// Most valuable victim, least valuable attacker. Promotions count the piece gained as part of the
+/// victim, so a quiet queen promotion sorts about level with taking a queen.
+pub fn mvv_lva(position: &Position, mov: &Move) -> i32 {
+    let victim = if mov.is_en_passant() {
+        Some(Piece::Pawn)
+    } else {
+        position.get(mov.target()).piece()
+    };
+
+    let attacker = position.get(mov.source()).piece().map_or(0, ordinal);
+    let mut gain = victim.map_or(0, ordinal);
+
+    if mov.is_promotion() {
+        gain += ordinal(mov.promotion_piece()) - ordinal(Piece::Pawn);
+    }
+
+    gain * 8 - attacker
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+    use itertools::Itertools;
+
+    use super::*;
+
+    fn pick_all(picker: &mut MovePicker, position: &Position) -> Vec<Move> {
+        let gen = MoveGenerator::new();
+        picker.iter(&gen, position).collect()
+    }
+
+    #[test]
+    fn picks_every_legal_move_exactly_once() {
+        let position = Position::new(BEN::new("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1"));
+        let hash = Move::new(E5, F7, MoveType::CAPTURE);
+        let killer = Move::new(A2, A3, MoveType::QUIET);
+        let mut picker = MovePicker::new().with_hash_move(Some(hash)).with_killers(&[killer]);
+
+        let picked = pick_all(&mut picker, &position);
+        let expected = MoveGenerator::new().generate_moves(&position);
+
+        similar_asserts::assert_eq!(picked.iter().copied().sorted().collect::<Vec<Move>>(), expected.into_iter().sorted().collect::<Vec<Move>>());
+        assert_eq!(picked[0], hash);
+    }
+
+    #[test]
+    fn captures_come_out_mvv_lva() {
+        // The pawn can take the queen or the knight, the rook can take the queen.
+        let position = Position::new(BEN::new("4k3/8/8/2q1n3/3P4/8/8/2R1K3 w - - 0 1"));
+        let mut picker = MovePicker::new();
+        let picked = pick_all(&mut picker, &position);
+
+        assert_eq!(picked[0], Move::new(D4, C5, MoveType::CAPTURE));
+        assert_eq!(picked[1], Move::new(C1, C5, MoveType::CAPTURE));
+        assert_eq!(picked[2], Move::new(D4, E5, MoveType::CAPTURE));
+        assert!(!picked[3].is_capture());
+    }
+
+    #[test]
+    fn killers_come_after_captures_and_before_quiets() {
+        let position = Position::new(BEN::new("4k3/8/8/4n3/3P4/8/8/4K3 w - - 0 1"));
+        let killer = Move::new(E1, F1, MoveType::QUIET);
+        let mut picker = MovePicker::new().with_killers(&[killer]);
+
+        assert_eq!(picker.next_move(&MoveGenerator::new(), &position), Some(Move::new(D4, E5, MoveType::CAPTURE)));
+        assert_eq!(picker.stage(), Stage::Captures);
+        assert_eq!(picker.next_move(&MoveGenerator::new(), &position), Some(killer));
+        assert_eq!(picker.stage(), Stage::Killers);
+
+        let rest = pick_all(&mut picker, &position);
+        assert!(!rest.contains(&killer));
+        assert_eq!(picker.stage(), Stage::Done);
+    }
+
+    #[test]
+    fn illegal_hash_move_and_killers_are_skipped() {
+        let position = Position::new(BEN::start_position());
+        let hash = Move::new(E2, E5, MoveType::QUIET);
+        let killer = Move::new(D1, D4, MoveType::QUIET);
+        let mut picker = MovePicker::new().with_hash_move(Some(hash)).with_killers(&[killer]);
+
+        let picked = pick_all(&mut picker, &position);
+        assert_eq!(picked.len(), 20);
+        assert!(!picked.contains(&hash));
+        assert!(!picked.contains(&killer));
+    }
+
+    #[test]
+    fn hash_move_is_picked_without_generating() {
+        let position = Position::new(BEN::start_position());
+        let hash = Move::new(E2, E4, MoveType::DOUBLE_PAWN);
+        let mut picker = MovePicker::new().with_hash_move(Some(hash));
+
+        assert_eq!(picker.next_move(&MoveGenerator::new(), &position), Some(hash));
+        assert!(picker.captures.is_none());
+        assert!(picker.quiets.is_none());
+    }
+
+    #[test]
+    fn quiets_come_out_by_history() {
+        let position = Position::new(BEN::start_position());
+        let favourite = Move::new(G1, F3, MoveType::QUIET);
+        let mut ordering = MoveOrdering::new();
+        ordering.update_history(favourite, 500);
+
+        let mut picker = MovePicker::new();
+        assert_eq!(picker.next_ordered_move(&MoveGenerator::new(), &position, &ordering), Some(favourite));
+    }
+
+    #[test]
+    fn nothing_is_generated_until_asked() {
+        let position = Position::new(BEN::start_position());
+        let mut picker = MovePicker::new();
+        assert!(picker.captures.is_none());
+
+        picker.next_move(&MoveGenerator::new(), &position);
+        assert!(picker.captures.is_some());
+        // The start position has no captures, so we've moved on to the quiets.
+        assert!(picker.quiets.is_some());
+
+        let position = Position::new(BEN::new("4k3/8/8/4n3/3P4/8/8/4K3 w - - 0 1"));
+        let mut picker = MovePicker::new();
+        picker.next_move(&MoveGenerator::new(), &position);
+        assert!(picker.quiets.is_none());
+
+        // The hash move is checked without generating anything.
+        let hash = Move::new(D4, E5, MoveType::CAPTURE);
+        let mut picker = MovePicker::new().with_hash_move(Some(hash));
+        assert_eq!(picker.next_move(&MoveGenerator::new(), &position), Some(hash));
+        assert!(picker.captures.is_none());
+        assert!(picker.quiets.is_none());
+    }
+}
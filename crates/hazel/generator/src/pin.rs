@@ -0,0 +1,151 @@
+use hazel_bitboard::bitboard::Bitboard;
+use hazel_bitboard::pextboard;
+use hazel_core::piece::Piece;
+use hazel_core::square::Square;
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+/// The absolute pins against our king. A pinned piece may only move along the ray between our
+/// king and the piece pinning it, which includes capturing the pinner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Pins {
+    pinned: Bitboard,
+    rays: [Bitboard; 64],
+}
+
+impl Pins {
+    pub fn for_position(position: &Position) -> Self {
+        let king = position.our_king();
+        let villain = position.villain();
+        let friendlies = position.friendlies();
+        let enemies = position.enemies();
+        let queens = position.queens_for(&villain);
+
+        // Our own pieces are transparent here, so these are all the sliders which would be
+        // checking us if not for our pieces in the way.
+        let pinners =
+            (pextboard::attacks_for(Piece::Bishop, king, enemies) & (position.bishops_for(&villain) | queens)) |
+            (pextboard::attacks_for(Piece::Rook, king, enemies) & (position.rooks_for(&villain) | queens));
+
+        let mut pinned = Bitboard::empty();
+        let mut rays = [Bitboard::full(); 64];
+
+        for pinner in pinners {
+            let ray = between(king, pinner);
+            let blockers = ray & friendlies;
+            // Two or more of our pieces in the way means none of them are pinned. None means it's
+            // a check, and that's `check`s problem.
+            if blockers.count() == 1 {
+                let sq = blockers.into_iter().next().unwrap();
+                pinned.set(sq);
+                rays[sq.index()] = ray | Bitboard::from(pinner);
+            }
+        }
+
+        Self { pinned, rays }
+    }
+
+    /// The squares the piece on `sq` may move to without exposing our king. Unpinned pieces may
+    /// go anywhere.
+    pub fn ray_for(&self, sq: Square) -> Bitboard {
+        self.rays[sq.index()]
+    }
+
+    pub fn allows(&self, mov: &Move) -> bool {
+        !self.pinned.is_set(mov.source()) || self.ray_for(mov.source()).is_set(mov.target())
+    }
+}
+
+/// The squares strictly between `a` and `b`, if they share a rank, file or diagonal. Empty
+/// otherwise.
+pub fn between(a: Square, b: Square) -> Bitboard {
+    let rank_delta = a.rank().abs_diff(b.rank());
+    let file_delta = a.file().abs_diff(b.file());
+
+    let line = if rank_delta == 0 || file_delta == 0 {
+        Piece::Rook
+    } else if rank_delta == file_delta {
+        Piece::Bishop
+    } else {
+        return Bitboard::empty();
+    };
+
+    // With only the endpoints as blockers, the squares both ends can see along the line are the
+    // ones in between.
+    let ends = Bitboard::from(a) | Bitboard::from(b);
+    pextboard::attacks_for(line, a, ends) & pextboard::attacks_for(line, b, ends)
+}
+
+/// En passant is the one move where two pieces leave the same rank at once, which can expose our
+/// king in a way the pin check doesn't see, so just try it and look.
+pub fn en_passant_is_safe(position: &Position, mov: &Move) -> bool {
+    let victim = Square::from((mov.source().rank(), mov.target().file()));
+
+    let mut blockers = position.all_blockers();
+    blockers.unset(mov.source());
+    blockers.unset(victim);
+    blockers.set(mov.target());
+
+    let attackers = position.attackers_of(position.our_king(), &position.villain(), blockers);
+    (attackers & !Bitboard::from(victim)).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_bitboard::bitboard;
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    #[test]
+    fn between_on_a_file() {
+        assert_eq!(between(A1, A5), bitboard!(A2, A3, A4));
+    }
+
+    #[test]
+    fn between_on_a_diagonal() {
+        assert_eq!(between(H8, D4), bitboard!(E5, F6, G7));
+    }
+
+    #[test]
+    fn between_unaligned_squares_is_empty() {
+        assert_eq!(between(A1, B3), Bitboard::empty());
+        assert_eq!(between(A1, A2), Bitboard::empty());
+    }
+
+    #[test]
+    fn finds_pins() {
+        let position = Position::new(BEN::new("4k3/8/8/b7/8/2N5/8/r2RK3 w - - 0 1"));
+        let pins = Pins::for_position(&position);
+
+        assert_eq!(pins.pinned, bitboard!(C3, D1));
+        assert_eq!(pins.ray_for(C3), bitboard!(D2, C3, B4, A5));
+        assert_eq!(pins.ray_for(D1), bitboard!(C1, D1, B1, A1));
+    }
+
+    #[test]
+    fn two_blockers_is_not_a_pin() {
+        let position = Position::new(BEN::new("4k3/8/8/b7/1P6/2N5/8/4K3 w - - 0 1"));
+        assert_eq!(Pins::for_position(&position).pinned, Bitboard::empty());
+    }
+
+    #[test]
+    fn pinned_piece_may_capture_the_pinner() {
+        let position = Position::new(BEN::new("4k3/8/8/8/8/8/8/r2RK3 w - - 0 1"));
+        let pins = Pins::for_position(&position);
+
+        assert!(pins.allows(&Move::new(D1, A1, MoveType::CAPTURE)));
+        assert!(!pins.allows(&Move::new(D1, D2, MoveType::QUIET)));
+    }
+
+    #[test]
+    fn en_passant_exposing_the_king_along_the_rank() {
+        let position = Position::new(BEN::new("8/8/8/KPp4r/8/8/8/7k w - c6 0 1"));
+        assert!(!en_passant_is_safe(&position, &Move::new(B5, C6, MoveType::EP_CAPTURE)));
+
+        let position = Position::new(BEN::new("8/8/8/KPp5/8/8/8/7k w - c6 0 1"));
+        assert!(en_passant_is_safe(&position, &Move::new(B5, C6, MoveType::EP_CAPTURE)));
+    }
+}
@@ -0,0 +1,250 @@
+use hazel_bitboard::bitboard::Bitboard;
+use hazel_bitboard::constants::move_tables::{KING_ATTACKS, KNIGHT_MOVES};
+use hazel_bitboard::pextboard;
+use hazel_core::color::{Color, COLOR_COUNT};
+use hazel_core::direction::Direction;
+use hazel_core::interface::Query;
+use hazel_core::piece::{Piece, PIECE_COUNT};
+use hazel_core::square::Square;
+use hazel_representation::coup::rep::Move;
+use hazel_representation::game::position::Position;
+
+/// Piece values used by the exchange evaluator, indexed by `Piece`. These are deliberately plain,
+/// SEE only needs to get the order of magnitude right.
+pub const SEE_VALUES: [i32; PIECE_COUNT] = [
+    320,    // Knight
+    330,    // Bishop
+    500,    // Rook
+    900,    // Queen
+    20_000, // King
+    100,    // Pawn
+];
+
+/// Least valuable first, the order attackers are pulled off the square in.
+const CAPTURE_ORDER: [Piece; PIECE_COUNT] = [
+    Piece::Pawn,
+    Piece::Knight,
+    Piece::Bishop,
+    Piece::Rook,
+    Piece::Queen,
+    Piece::King,
+];
+
+/// Static exchange evaluation of `square` for the side to move. Both sides capture there with
+/// their least valuable piece in turn, and either may stop whenever carrying on would lose
+/// material, so this is never negative. 0 if there's nothing of theirs to take.
+///
+/// NOTE: Pins and checks are ignored, as is promoting on the last rank. This is an estimate.
+pub fn see(position: &Position, square: Square) -> i32 {
+    let exchange = Exchange::new(position);
+    let hero = position.hero();
+
+    let Some(victim) = position.get(square).piece() else { return 0; };
+    if position.get(square).color() == Some(hero) { return 0; }
+
+    let attackers = exchange.attackers(square, exchange.occupied);
+    match exchange.least_valuable(attackers, hero) {
+        Some((source, _)) => exchange.swap(square, source, SEE_VALUES[victim as usize]).max(0),
+        None => 0,
+    }
+}
+
+/// Static exchange evaluation of a particular capture. The capture itself is forced, after that
+/// it's the same as `see`, so a capture of a defended piece with something more valuable comes
+/// out negative.
+pub fn see_capture(position: &Position, mov: &Move) -> i32 {
+    let mut exchange = Exchange::new(position);
+
+    let victim = if mov.is_en_passant() {
+        // The victim isn't on the target square, so just take it off the board.
+        exchange.occupied.unset(Square::from((mov.source().rank(), mov.target().file())));
+        Some(Piece::Pawn)
+    } else {
+        position.get(mov.target()).piece()
+    };
+
+    exchange.swap(mov.target(), mov.source(), victim.map_or(0, |piece| SEE_VALUES[piece as usize]))
+}
+
+/// The piece sets we need, pulled off the position once.
+struct Exchange {
+    pieces: [[Bitboard; PIECE_COUNT]; COLOR_COUNT],
+    occupied: Bitboard,
+}
+
+impl Exchange {
+    fn new(position: &Position) -> Self {
+        let occupied = position.all_blockers();
+        let mut pieces = [[Bitboard::empty(); PIECE_COUNT]; COLOR_COUNT];
+
+        for sq in occupied {
+            let occupant = position.get(sq);
+            if let (Some(piece), Some(color)) = (occupant.piece(), occupant.color()) {
+                pieces[color as usize][piece as usize].set(sq);
+            }
+        }
+
+        Self { pieces, occupied }
+    }
+
+    fn of(&self, color: Color, piece: Piece) -> Bitboard {
+        self.pieces[color as usize][piece as usize]
+    }
+
+    fn both(&self, piece: Piece) -> Bitboard {
+        self.of(Color::WHITE, piece) | self.of(Color::BLACK, piece)
+    }
+
+    /// Everything, of either color, attacking `square` through `occupied`. Sliders are looked up
+    /// against `occupied`, so as pieces come off the board, the ones behind them show up.
+    fn attackers(&self, square: Square, occupied: Bitboard) -> Bitboard {
+        let queens = self.both(Piece::Queen);
+        let diagonals = pextboard::attacks_for(Piece::Bishop, square, occupied) & (self.both(Piece::Bishop) | queens);
+        let orthogonals = pextboard::attacks_for(Piece::Rook, square, occupied) & (self.both(Piece::Rook) | queens);
+
+        let mut pawns = Bitboard::empty();
+        for color in [Color::WHITE, Color::BLACK] {
+            let behind = Bitboard::from(square).shift((!color).pawn_direction());
+            pawns |= (behind.shift(Direction::E) | behind.shift(Direction::W)) & self.of(color, Piece::Pawn);
+        }
+
+        (pawns |
+         (KNIGHT_MOVES[square.index()] & self.both(Piece::Knight)) |
+         (KING_ATTACKS[square.index()] & self.both(Piece::King)) |
+         diagonals |
+         orthogonals) & occupied
+    }
+
+    fn least_valuable(&self, attackers: Bitboard, color: Color) -> Option<(Square, Piece)> {
+        CAPTURE_ORDER.into_iter().find_map(|piece| {
+            (attackers & self.of(color, piece)).into_iter().next().map(|sq| (sq, piece))
+        })
+    }
+
+    /// The swap algorithm, see https://www.chessprogramming.org/SEE_-_The_Swap_Algorithm.
+    /// `first` is forced to capture the piece worth `victim` on `square`.
+    fn swap(&self, square: Square, first: Square, victim: i32) -> i32 {
+        let mut occupied = self.occupied;
+
+        let mut color = self.color_on(first);
+        let mut attacker = (first, self.piece_on(first));
+
+        // gain[d] is the balance for whoever captures at depth d, if the exchange stops there.
+        let mut gain = vec![victim];
+
+        loop {
+            let (source, piece) = attacker;
+            gain.push(SEE_VALUES[piece as usize] - gain[gain.len() - 1]);
+
+            occupied.unset(source);
+            let attackers = self.attackers(square, occupied);
+            color = !color;
+
+            let Some(next) = self.least_valuable(attackers, color) else { break; };
+
+            // The king can only capture if nothing is left to capture it back.
+            if next.1 == Piece::King && (attackers & self.side(!color)).is_nonempty() { break; }
+
+            attacker = next;
+        }
+
+        // The last entry is the value of a capture nobody gets to make, drop it and unwind.
+        gain.pop();
+        while gain.len() > 1 {
+            let last = gain.pop().unwrap();
+            let prev = gain.len() - 1;
+            gain[prev] = -(-gain[prev]).max(last);
+        }
+
+        gain[0]
+    }
+
+    fn side(&self, color: Color) -> Bitboard {
+        self.pieces[color as usize].iter().fold(Bitboard::empty(), |acc, bb| acc | *bb)
+    }
+
+    fn color_on(&self, sq: Square) -> Color {
+        if self.side(Color::WHITE).is_set(sq) { Color::WHITE } else { Color::BLACK }
+    }
+
+    fn piece_on(&self, sq: Square) -> Piece {
+        CAPTURE_ORDER.into_iter().find(|piece| self.both(*piece).is_set(sq)).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::MoveType;
+
+    use super::*;
+
+    const PAWN: i32 = SEE_VALUES[Piece::Pawn as usize];
+    const KNIGHT: i32 = SEE_VALUES[Piece::Knight as usize];
+    const BISHOP: i32 = SEE_VALUES[Piece::Bishop as usize];
+    const ROOK: i32 = SEE_VALUES[Piece::Rook as usize];
+
+    fn position(fen: &str) -> Position {
+        Position::new(BEN::new(fen))
+    }
+
+    #[test]
+    fn undefended_pawn() {
+        let position = position("1k1r4/1pp4p/p7/4p3/8/P5P1/1PP4P/2K1R3 w - - 0 1");
+        assert_eq!(see(&position, E5), PAWN);
+        assert_eq!(see_capture(&position, &Move::new(E1, E5, MoveType::CAPTURE)), PAWN);
+    }
+
+    #[test]
+    fn knight_for_a_pawn() {
+        // From the chessprogramming wiki SEE page.
+        let position = position("1k1r3q/1ppn3p/p4b2/4p3/8/P2N2P1/1PP1R1BP/2K1Q3 w - - 0 1");
+        assert_eq!(see_capture(&position, &Move::new(D3, E5, MoveType::CAPTURE)), PAWN - KNIGHT);
+        assert_eq!(see(&position, E5), 0);
+    }
+
+    #[test]
+    fn nothing_to_take() {
+        let position = position("4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(see(&position, A8), 0);
+        assert_eq!(see(&position, A1), 0);
+    }
+
+    #[test]
+    fn xray_rooks() {
+        // Doubled rooks win the pawn despite the rook defending it.
+        let position = position("3rk3/8/8/3p4/8/8/3R4/3RK3 w - - 0 1");
+        assert_eq!(see(&position, D5), PAWN);
+
+        // One rook alone just loses the exchange for a pawn.
+        let position = self::position("3rk3/8/8/3p4/8/8/8/3RK3 w - - 0 1");
+        assert_eq!(see(&position, D5), 0);
+        assert_eq!(see_capture(&position, &Move::new(D1, D5, MoveType::CAPTURE)), PAWN - ROOK);
+    }
+
+    #[test]
+    fn xray_through_a_bishop() {
+        // The queen behind the bishop wins the knight back.
+        let position = position("4k3/3n4/8/4p3/8/8/1B6/Q3K3 w - - 0 1");
+        assert_eq!(see_capture(&position, &Move::new(B2, E5, MoveType::CAPTURE)), PAWN - BISHOP + KNIGHT);
+
+        let position = self::position("4k3/3n4/8/4p3/8/8/1B6/4K3 w - - 0 1");
+        assert_eq!(see_capture(&position, &Move::new(B2, E5, MoveType::CAPTURE)), PAWN - BISHOP);
+    }
+
+    #[test]
+    fn king_cannot_recapture_a_defended_piece() {
+        let position = position("8/4k3/3p4/8/8/8/3R4/3RK3 w - - 0 1");
+        assert_eq!(see(&position, D6), PAWN);
+
+        let position = self::position("8/4k3/3p4/8/8/8/8/3RK3 w - - 0 1");
+        assert_eq!(see_capture(&position, &Move::new(D1, D6, MoveType::CAPTURE)), PAWN - ROOK);
+    }
+
+    #[test]
+    fn en_passant() {
+        let position = position("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1");
+        assert_eq!(see_capture(&position, &Move::new(D5, E6, MoveType::EP_CAPTURE)), PAWN);
+    }
+}
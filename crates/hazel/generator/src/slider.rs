@@ -1,41 +1,45 @@
 use hazel_core::occupant::Occupant;
 use hazel_core::piece::Piece;
+use hazel_bitboard::bitboard::Bitboard;
 use hazel_bitboard::pextboard;
 use hazel_representation::game::position::Position;
 use hazel_representation::coup::rep::{Move, MoveType};
 
+use crate::mode::Targets;
+
 pub mod bishop {
 
 
     use super::*;
-    pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
-        generate_slider_moves(position, Piece::Bishop)
+    pub fn generate_moves(position: &Position, targets: &Targets) -> impl Iterator<Item = Move> {
+        generate_slider_moves(position, Piece::Bishop, targets)
     }
 }
 
 pub mod rook {
     use super::*;
-    pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
-        generate_slider_moves(position, Piece::Rook)
+    pub fn generate_moves(position: &Position, targets: &Targets) -> impl Iterator<Item = Move> {
+        generate_slider_moves(position, Piece::Rook, targets)
     }
 }
 
 pub mod queen {
     use super::*;
-    pub fn generate_moves(position: &Position) -> impl Iterator<Item = Move> {
-        generate_slider_moves(position, Piece::Queen)
+    pub fn generate_moves(position: &Position, targets: &Targets) -> impl Iterator<Item = Move> {
+        generate_slider_moves(position, Piece::Queen, targets)
     }
 }
 
-fn generate_slider_moves(position: &Position, piece: Piece) -> impl Iterator<Item = Move> {
+fn generate_slider_moves(position: &Position, piece: Piece, targets: &Targets) -> impl Iterator<Item = Move> {
     let pieces = position.find(|(_sq, occ)| *occ == Occupant::Occupied(piece, position.hero()));
     let blockers = position.all_blockers();
-    let enemies = position.enemies();
-    let friendlies = position.friendlies();
+    let enemies = if targets.tactical() { position.enemies() } else { Bitboard::empty() };
+    let empty = if targets.quiet() { !blockers } else { Bitboard::empty() };
+    let targets = *targets;
 
     pieces.into_iter().flat_map(move |source_sq| {
-        let moves = pextboard::attacks_for(piece, source_sq, blockers) & !friendlies;
-        (moves & !enemies).into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::QUIET)).chain(
+        let moves = pextboard::attacks_for(piece, source_sq, blockers) & targets.mask(piece, source_sq) & (enemies | empty);
+        (moves & empty).into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::QUIET)).chain(
         (moves & enemies).into_iter().map(move |target_sq| Move::new(source_sq, target_sq, MoveType::CAPTURE)))
     })
 }
@@ -52,7 +56,7 @@ mod tests {
     #[test]
     fn bishop_test_position() {
         let position = Position::new(BEN::new("8/8/1p3p2/8/3B4/2P1P3/8/8 w - - 0 1"));
-        let moves = bishop::generate_moves(&position);
+        let moves = bishop::generate_moves(&position, &Targets::all());
         let mut expected = vec![
             Move::new(D4, C5, MoveType::QUIET),
             Move::new(D4, E5, MoveType::QUIET),
@@ -67,7 +71,7 @@ mod tests {
     #[test]
     fn queen_test_position() {
         let position = Position::new(BEN::new("8/3p4/1p3p2/8/2PQP3/2PPP3/8/8 w - - 0 1"));
-        let moves = queen::generate_moves(&position);
+        let moves = queen::generate_moves(&position, &Targets::all());
         let mut expected =  vec![
             Move::new(D4, C5, MoveType::QUIET),
             Move::new(D4, E5, MoveType::QUIET),
@@ -85,7 +89,7 @@ mod tests {
     #[test]
     fn rook_test_position() {
         let position = Position::new(BEN::new("8/8/3p4/8/3R1p2/3P4/8/3P4 w - - 0 1"));
-        let moves = rook::generate_moves(&position);
+        let moves = rook::generate_moves(&position, &Targets::all());
         let mut expected = vec![
             Move::new(D4, C4, MoveType::QUIET),
             Move::new(D4, B4, MoveType::QUIET),
@@ -0,0 +1,83 @@
+use hazel_representation::game::position::Position;
+use hazel_representation::game::reason::Reason;
+
+use crate::MoveGenerator;
+
+/// Why the game is over at `position`, or `None` if it isn't.
+///
+/// Mate and stalemate come first, so a mate on the fiftieth move still counts. Draws which the
+/// rules only allow a player to claim (threefold, fifty moves) are taken as soon as they can be.
+/// Resignation, timeouts and agreements aren't in the position, so never come from here.
+pub fn terminal(position: &Position) -> Option<Reason> {
+    if MoveGenerator::new().generate_moves(position).is_empty() {
+        return Some(if position.checkers().is_nonempty() {
+            Reason::Checkmate(position.villain())
+        } else {
+            Reason::Stalemate
+        });
+    }
+
+    if position.is_threefold_repetition() {
+        Some(Reason::ThreefoldRepetition)
+    } else if position.is_fifty_move_draw() {
+        Some(Reason::FiftyMoveRule)
+    } else if position.is_insufficient_material() {
+        Some(Reason::InsufficientMaterial)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hazel_core::ben::BEN;
+    use hazel_core::color::Color;
+    use hazel_core::square::*;
+    use hazel_representation::coup::rep::{Move, MoveType};
+
+    use super::*;
+
+    fn terminal_at(fen: &str) -> Option<Reason> {
+        terminal(&Position::new(BEN::new(fen)))
+    }
+
+    #[test]
+    fn games_in_progress() {
+        assert_eq!(terminal_at("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), None);
+    }
+
+    #[test]
+    fn checkmate() {
+        assert_eq!(terminal_at("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1"), Some(Reason::Checkmate(Color::WHITE)));
+        assert_eq!(terminal_at("6k1/5ppp/8/8/8/8/5PPP/r5K1 w - - 0 1"), Some(Reason::Checkmate(Color::BLACK)));
+    }
+
+    #[test]
+    fn mate_beats_the_fifty_move_rule() {
+        assert_eq!(terminal_at("R5k1/5ppp/8/8/8/8/8/6K1 b - - 100 80"), Some(Reason::Checkmate(Color::WHITE)));
+        assert_eq!(terminal_at("6k1/5ppp/8/8/8/8/8/R5K1 b - - 100 80"), Some(Reason::FiftyMoveRule));
+    }
+
+    #[test]
+    fn stalemate() {
+        assert_eq!(terminal_at("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1"), Some(Reason::Stalemate));
+    }
+
+    #[test]
+    fn insufficient_material() {
+        assert_eq!(terminal_at("4k3/8/8/8/8/8/8/2N1K3 b - - 0 1"), Some(Reason::InsufficientMaterial));
+    }
+
+    #[test]
+    fn threefold_repetition() {
+        let shuffle = [
+            Move::new(G1, F3, MoveType::QUIET), Move::new(G8, F6, MoveType::QUIET),
+            Move::new(F3, G1, MoveType::QUIET), Move::new(F6, G8, MoveType::QUIET),
+        ];
+        let mut position = Position::new(BEN::start_position());
+        for mov in shuffle.iter().chain(shuffle.iter()) { position.make(*mov); }
+
+        assert_eq!(terminal(&position), Some(Reason::ThreefoldRepetition));
+        assert_eq!(terminal(&position).unwrap().to_pgn(), "1/2-1/2");
+    }
+}
@@ -12,6 +12,7 @@ use nom::{branch::alt, bytes::complete::tag, character::complete::{multispace0,
 
 use hazel_representation::game::familiar::Familiar;
 use hazel_representation::{coup::rep::Move, game::variation::Variation};
+use hazel_representation::game::reason::Reason;
 use crate::san::SAN;
 use crate::pgn::tokenizer::PGNToken;
 
@@ -51,6 +52,17 @@ impl PGN {
         self.variation.familiar()
     }
 
+    /// Why the game ended, if it has. The result only says who won, so this is as specific as the
+    /// `Termination` tag allows, see `with_termination`.
+    pub fn result(&self) -> Option<Reason> {
+        self.variation.result()
+    }
+
+    /// Where the game started, and the moves actually played, see `Variation::mainline`.
+    pub fn mainline(&self) -> (BEN, Vec<Move>) {
+        self.variation.mainline()
+    }
+
     pub fn current_position(&self) -> BEN {
         let mut v = self.variation.clone();
         v.current_position()
@@ -86,6 +98,10 @@ impl PGN {
                     variation.make(san.try_into().unwrap()).commit();
                 },
                 PGNToken::Halt(reason) => {
+                    let reason = match pgn.tag_pairs.iter().find(|tp| tp.name == "Termination") {
+                        Some(termination) => with_termination(reason, &termination.value),
+                        None => reason,
+                    };
                     variation.halt(reason).commit();
                 },
                 PGNToken::GameEnd => {
@@ -103,8 +119,34 @@ impl PGN {
     }
 }
 
+/// Narrows down a PGN result with the `Termination` tag, if it says anything we recognize.
+///
+/// The standard only has `normal`, `time forfeit` and the like, but plenty of sites write things
+/// like `black won on time` or `Game drawn by repetition`, so this looks for keywords. Anything
+/// which doesn't agree with the result (e.g., a `1-0` "drawn by agreement") is ignored.
+fn with_termination(reason: Reason, termination: &str) -> Reason {
+    let termination = termination.to_ascii_lowercase();
+    let mentions = |words: &[&str]| words.iter().any(|word| termination.contains(word));
+
+    match reason.winner() {
+        Some(winner) if mentions(&["checkmate"]) => Reason::Checkmate(winner),
+        Some(winner) if mentions(&["resign"]) => Reason::Resignation(winner),
+        Some(winner) if mentions(&["time", "forfeit"]) => Reason::Timeout(winner),
+        Some(_) => reason,
+        None if !reason.is_draw() => reason,
+        None if mentions(&["stalemate"]) => Reason::Stalemate,
+        None if mentions(&["repetition"]) => Reason::ThreefoldRepetition,
+        None if mentions(&["50", "fifty"]) => Reason::FiftyMoveRule,
+        None if mentions(&["insufficient"]) => Reason::InsufficientMaterial,
+        None if mentions(&["agree"]) => Reason::Agreement,
+        None => reason,
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use hazel_core::color::Color;
+
     use super::*;
 
     mod pgn {
@@ -115,11 +157,14 @@ mod tests {
             let pgn = PGN::load("../../../tests/fixtures/no-variations-and-halts.pgn").unwrap();
 
             similar_asserts::assert_eq!(pgn.current_position(), BEN::new("3r2k1/5rp1/p3Q2p/1p2Bp2/8/PP1q4/4RPbP/4K3 w - - 2 30"));
+            // [Termination "black won on time"]
+            assert_eq!(pgn.result(), Some(Reason::Timeout(Color::BLACK)));
         }
 
         #[test]
         fn imports_from_pgn_with_no_variations_and_halt() {
             let pgn = PGN::load("../../../tests/fixtures/no-variations-and-no-halt.pgn").unwrap();
+            assert_eq!(pgn.result(), None);
 
             assert_eq!(pgn.current_position(), BEN::new("3r2k1/5rp1/p3Q2p/1p2Bp2/8/PP1q4/4RPbP/4K3 w - - 2 30"));
         }
@@ -138,6 +183,16 @@ mod tests {
             assert_eq!(pgn.current_position(), BEN::new("1rbqkb1r/pp2p2p/2p2pp1/3p3n/2PP4/4PN2/PP3PPP/RN1QKB1R w KQk - 0 8"));
         }
 
+        #[test]
+        fn mainline_skips_variations() {
+            let pgn = PGN::load("../../../tests/fixtures/with-variations-halts.pgn").unwrap();
+            let (start, moves) = pgn.mainline();
+
+            assert_eq!(start, BEN::start_position());
+            assert_eq!(moves.len(), 14);
+            assert_eq!(moves.iter().map(|mov| mov.to_uci()).take(4).collect::<Vec<_>>(), vec!["d2d4", "d7d5", "c1f4", "c7c6"]);
+        }
+
         #[test]
         fn imports_from_pgn_with_nested_variations_and_no_halt() {
             let pgn = PGN::load("../../../tests/fixtures/with-nested-variations-no-halt.pgn").unwrap();
@@ -152,4 +207,32 @@ mod tests {
             assert_eq!(pgn.current_position(), BEN::new("1rbqkb1r/pp2p2p/2p2pp1/3p3n/2PP4/4PN2/PP3PPP/RN1QKB1R w KQk - 0 8"));
         }
     }
+
+    mod termination {
+        use super::*;
+
+        #[test]
+        fn decisive_results() {
+            let white = Reason::Winner(Color::WHITE);
+            assert_eq!(with_termination(white, "white won by checkmate"), Reason::Checkmate(Color::WHITE));
+            assert_eq!(with_termination(white, "White won by resignation"), Reason::Resignation(Color::WHITE));
+            assert_eq!(with_termination(white, "Time forfeit"), Reason::Timeout(Color::WHITE));
+            assert_eq!(with_termination(white, "Normal"), white);
+        }
+
+        #[test]
+        fn draws() {
+            assert_eq!(with_termination(Reason::Draw, "Game drawn by stalemate"), Reason::Stalemate);
+            assert_eq!(with_termination(Reason::Draw, "Game drawn by repetition"), Reason::ThreefoldRepetition);
+            assert_eq!(with_termination(Reason::Draw, "Game drawn by 50-move rule"), Reason::FiftyMoveRule);
+            assert_eq!(with_termination(Reason::Draw, "Game drawn by timeout vs insufficient material"), Reason::InsufficientMaterial);
+            assert_eq!(with_termination(Reason::Draw, "Game drawn by agreement"), Reason::Agreement);
+        }
+
+        #[test]
+        fn disagreements_are_ignored() {
+            assert_eq!(with_termination(Reason::Winner(Color::BLACK), "Game drawn by agreement"), Reason::Winner(Color::BLACK));
+            assert_eq!(with_termination(Reason::Aborted, "white won on time"), Reason::Aborted);
+        }
+    }
 }
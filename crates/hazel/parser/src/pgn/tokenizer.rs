@@ -1,4 +1,3 @@
-use hazel_core::color::Color;
 use nom::{branch::alt, bytes::complete::tag, character::complete::{char, alpha1, multispace0, newline, one_of}, combinator::opt, multi::many1, sequence::delimited, IResult};
 
 use hazel_representation::game::reason::Reason;
@@ -108,16 +107,9 @@ impl PGNToken {
         // chew up any whitespace
         let (input, _) = multispace0(input)?;
 
-        match is_halt {
-            Some(r) => {
-                let reason = match r {
-                    "0-1" => Reason::Winner(Color::BLACK),
-                    "1-0" => Reason::Winner(Color::WHITE),
-                    "1/2-1/2" => Reason::Stalemate,
-                    _ => Reason::Aborted
-                };
-                Ok((input, PGNToken::Halt(reason)))
-            },
+        // The result only says who won, not why, see `Reason::from_pgn`.
+        match is_halt.and_then(Reason::from_pgn) {
+            Some(reason) => Ok((input, PGNToken::Halt(reason))),
             None => Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag))),
         }
     }
@@ -176,6 +168,8 @@ impl PGNToken {
 
 #[cfg(test)]
 mod tests {
+    use hazel_core::color::Color;
+
     use super::*;
 
     mod tokenizer {
@@ -511,7 +505,7 @@ mod tests {
 
             let (input, token) = PGNToken::halt("1/2-1/2 ").unwrap();
             assert_eq!(input, "");
-            assert_eq!(token, PGNToken::Halt(Reason::Stalemate));
+            assert_eq!(token, PGNToken::Halt(Reason::Draw));
 
             let (input, token) = PGNToken::halt("* ").unwrap();
             assert_eq!(input, "");
@@ -15,10 +15,20 @@ pub struct UCI {
 
 impl From<UCI> for Move {
     fn from(uci: UCI) -> Self {
+        // UCI_AMBIGUOUS has no room for the promotion piece, so promotions come through as quiet
+        // promotions. `MoveGenerator::is_legal` will set the capture flag if it's needed.
+        let metadata = match uci.promotion_piece {
+            Some(Piece::Knight) => MoveType::PROMOTION_KNIGHT,
+            Some(Piece::Bishop) => MoveType::PROMOTION_BISHOP,
+            Some(Piece::Rook) => MoveType::PROMOTION_ROOK,
+            Some(Piece::Queen) => MoveType::PROMOTION_QUEEN,
+            _ => uci.metadata,
+        };
+
         Move::new(
             uci.source,
             uci.target,
-            uci.metadata
+            metadata
         )
     }
 }
@@ -115,6 +125,13 @@ mod tests {
         assert_eq!(uci.metadata, MoveType::UCI_AMBIGUOUS);
     }
 
+    #[test]
+    fn promotion_piece_survives_into_the_move() {
+        let mov : Move = UCI::try_from("e7e8n").unwrap().into();
+        assert_eq!(mov, Move::new(E7, E8, MoveType::PROMOTION_KNIGHT));
+        assert_eq!(mov.to_uci(), "e7e8n");
+    }
+
     #[test]
     fn promotion_invalid() {
         assert!(UCI::try_from("e7e8q1").is_err());
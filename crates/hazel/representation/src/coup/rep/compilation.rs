@@ -80,33 +80,13 @@ impl Move {
                 this.halfmove_clock += 1;
             }
 
-            let source = mov.source();
-            match piece {
-                Piece::King => {
-                    match color  {
-                        Color::WHITE => {
-                            this.castling.white_short = false;
-                            this.castling.white_long = false;
-                        }
-                        Color::BLACK => {
-                            this.castling.black_short = false;
-                            this.castling.black_long = false;
-                        }
-                    }
-                }
-                Piece::Rook if source == H1 => { this.castling.white_short = false; }
-                Piece::Rook if source == H8 => { this.castling.black_short = false; }
-                Piece::Rook if source == A1 => { this.castling.white_long = false; }
-                Piece::Rook if source == A8 => { this.castling.black_long = false; }
-                Piece::Rook => {}
-                Piece::Pawn => {
-                    this.en_passant = if mov.is_double_pawn_push_for(color) {
-                        mov.target().shift(color.pawn_direction()).map(|target| File::from(target.file()))
-                    } else {
-                        None
-                    }
+            this.castling.update(piece, color, mov.source(), mov.target());
+            if piece == Piece::Pawn {
+                this.en_passant = if mov.is_double_pawn_push_for(color) {
+                    mov.target().shift(color.pawn_direction()).map(|target| File::from(target.file()))
+                } else {
+                    None
                 }
-                _ => {}
             }
         };
         ret.push(Alteration::Inform(new_metadata));
@@ -269,11 +249,15 @@ impl Move {
                 Alteration::remove(target, target_occupant),
                 Alteration::place(target, source_occupant),
             ],
-            MoveType::EP_CAPTURE => vec![
-                Alteration::remove(source, source_occupant),
-                Alteration::remove(target, target_occupant),
-                Alteration::place(target, source_occupant),
-            ],
+            MoveType::EP_CAPTURE => {
+                // The captured pawn isn't on the target square, it's beside the source.
+                let victim = Square::from((source.rank(), target.file()));
+                vec![
+                    Alteration::remove(source, source_occupant),
+                    Alteration::remove(victim, context.get(victim)),
+                    Alteration::place(target, source_occupant),
+                ]
+            },
             MoveType::PROMOTION_KNIGHT => vec![
                 Alteration::remove(source, source_occupant),
                 Alteration::place(target, Occupant::knight(source_occupant.color().unwrap())),
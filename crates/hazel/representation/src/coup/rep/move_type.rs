@@ -107,7 +107,6 @@ impl MoveType {
 
     const PROMOTION_MASK: u16 = 0b1000;
     const CAPTURE_MASK: u16 = 0b0100;
-    const EP_MASK: u16 = 0b0101;
 
     #[inline(always)]
     pub fn is_long_castle(self) -> bool {
@@ -132,7 +131,8 @@ impl MoveType {
 
     #[inline(always)]
     pub fn is_en_passant(self) -> bool {
-        (self as u16 & Self::EP_MASK) != 0
+        // NOTE: EP_CAPTURE shares bits with both DOUBLE_PAWN and CAPTURE, so this can't be a mask.
+        self == MoveType::EP_CAPTURE
     }
 
     // convenience constructors
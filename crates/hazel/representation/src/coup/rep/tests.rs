@@ -306,6 +306,12 @@ mod proxy_methods {
         assert!(m.is_en_passant());
     }
 
+    #[test]
+    fn captures_and_double_pawns_are_not_en_passant() {
+        assert!(!Move::new(D6, E7, MoveType::CAPTURE).is_en_passant());
+        assert!(!Move::new(D2, D4, MoveType::DOUBLE_PAWN).is_en_passant());
+    }
+
     #[test]
     fn is_double_pawn_push_for() {
         let m = Move::new(D2, D4, MoveType::DOUBLE_PAWN);
@@ -351,4 +357,33 @@ mod compilation {
 
         similar_asserts::assert_eq!(mov.new_compile(&board, &meta), expected_alterations);
     }
+
+    #[test]
+    fn en_passant_removes_the_captured_pawn() {
+        let mut board = PieceBoard::default();
+        board.set_fen(BEN::new("4k3/8/8/3Pp3/8/8/8/4K3 w - e6 0 1"));
+
+        let mov = Move::new(D5, E6, MoveType::EP_CAPTURE);
+
+        similar_asserts::assert_eq!(mov.compile(&board), vec![
+            Alteration::remove(D5, Occupant::white_pawn()),
+            Alteration::remove(E5, Occupant::black_pawn()),
+            Alteration::place(E6, Occupant::white_pawn()),
+        ]);
+    }
+
+    #[test]
+    fn capturing_a_rook_on_its_home_square_removes_the_castle_right() {
+        let ben = BEN::new("r3k2r/8/8/8/8/8/6B1/R3K2R w KQkq - 0 1");
+        let mut board = PieceBoard::default();
+        board.set_fen(ben);
+
+        let mov = Move::new(G2, A8, MoveType::CAPTURE);
+        let alterations = mov.new_compile(&board, &ben.metadata());
+
+        let Some(Alteration::Inform(meta)) = alterations.last() else { panic!("Expected an Inform") };
+        assert!(!meta.castling.black_long);
+        assert!(meta.castling.black_short);
+        assert!(meta.castling.white_short && meta.castling.white_long);
+    }
 }
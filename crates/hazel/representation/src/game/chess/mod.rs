@@ -14,7 +14,6 @@ use hazel_core::interface::{Alter, Query};
 use hazel_core::occupant::Occupant;
 use hazel_core::piece::Piece;
 use hazel_core::position_metadata::PositionMetadata;
-use hazel_core::square::*;
 
 use crate::coup::rep::Move;
 use crate::extensions::query::display_board;
@@ -83,33 +82,13 @@ impl<T> Play for ChessGame<T> where T: Alter + Query + Default + Clone {
                         this.halfmove_clock += 1;
                     }
 
-                    let source = mov.source();
-                    match piece {
-                        Piece::King => {
-                            match color  {
-                                Color::WHITE => {
-                                    this.castling.white_short = false;
-                                    this.castling.white_long = false;
-                                }
-                                Color::BLACK => {
-                                    this.castling.black_short = false;
-                                    this.castling.black_long = false;
-                                }
-                            }
+                    this.castling.update(piece, color, mov.source(), mov.target());
+                    if piece == Piece::Pawn {
+                        this.en_passant = if mov.is_double_pawn_push_for(color) {
+                            mov.target().shift(color.pawn_direction()).map(|target| File::from(target.file()))
+                        } else {
+                            None
                         }
-                        Piece::Rook if source == H1 => { this.castling.white_short = false; }
-                        Piece::Rook if source == H8 => { this.castling.black_short = false; }
-                        Piece::Rook if source == A1 => { this.castling.white_long = false; }
-                        Piece::Rook if source == A8 => { this.castling.black_long = false; }
-                        Piece::Rook => {}
-                        Piece::Pawn => {
-                            this.en_passant = if mov.is_double_pawn_push_for(color) {
-                                mov.target().shift(color.pawn_direction()).map(|target| File::from(target.file()))
-                            } else {
-                                None
-                            }
-                        }
-                        _ => {}
                     }
                 };
                 for a in alts {
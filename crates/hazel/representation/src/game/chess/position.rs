@@ -2,7 +2,7 @@ use std::sync::Arc;
 use std::{fmt::Debug, sync::RwLock};
 
 use hazel_core::ben::BEN;
-use hazel_core::color::Color;
+use hazel_core::color::{Color, COLORS};
 use hazel_core::direction::Direction;
 use hazel_core::interface::query::to_fen_position;
 use hazel_core::occupant::Occupant;
@@ -209,6 +209,91 @@ impl Position {
         *fam.get()
     }
 
+    /// The position hash before each turn back to the last irreversible move (a capture or a pawn
+    /// move, per the halfmove clock), oldest first, ending with the current position.
+    ///
+    /// Nothing from before an irreversible move can come up again, so that's as far back as
+    /// repetitions need to look. The walk goes backwards from the writehead, every alteration is
+    /// an XOR into the hash, so undoing a turn is just applying it again.
+    pub fn zobrist_history(&self) -> Vec<Zobrist> {
+        let mut hash = self.zobrist().position;
+        let mut history = vec![hash];
+        let reversible_turns = self.metadata().halfmove_clock as usize;
+
+        let tape = self.tape.read().unwrap();
+        for alter in tape[..tape.writehead()].iter().rev() {
+            if history.len() > reversible_turns { break; }
+
+            match alter {
+                Alteration::Turn => history.push(hash),
+                // The start of the setup, there are no more turns.
+                Alteration::Clear => break,
+                _ => { hash.alter_mut(*alter); }
+            }
+        }
+
+        history.reverse();
+        history
+    }
+
+    /// How many times the current position has come up before.
+    ///
+    /// NOTE: The en passant file is part of the hash whenever it's set, even if no pawn can take,
+    /// so the position right after a double push won't match the same position later on.
+    /// Stricter than the rules, but never claims a repetition that isn't one.
+    pub fn repetitions(&self) -> usize {
+        // It takes at least four reversible turns to get back to where we started.
+        if self.metadata().halfmove_clock < 4 { return 0; }
+
+        let mut history = self.zobrist_history();
+        let Some(current) = history.pop() else { return 0; };
+        history.iter().filter(|hash| **hash == current).count()
+    }
+
+    /// The current position has come up before. A search can treat this as a draw, since
+    /// whatever got us here can be repeated until it's a threefold.
+    pub fn is_repetition(&self) -> bool {
+        self.repetitions() >= 1
+    }
+
+    /// The current position has come up for (at least) the third time.
+    pub fn is_threefold_repetition(&self) -> bool {
+        self.repetitions() >= 2
+    }
+
+    /// Fifty moves each without a capture or a pawn move.
+    pub fn is_fifty_move_draw(&self) -> bool {
+        self.metadata().halfmove_clock >= 100
+    }
+
+    /// A threefold repetition or the fifty-move rule.
+    ///
+    /// NOTE: A move which checkmates still wins, even if it's the one that hits the fifty-move
+    /// rule. `Position` doesn't know what's legal, so check for mate first.
+    pub fn is_draw_by_rule(&self) -> bool {
+        self.is_fifty_move_draw() || self.is_threefold_repetition()
+    }
+
+    /// Neither side could mate, however badly the other played: bare kings, a single minor
+    /// piece, or only bishops, all on the same color of square.
+    ///
+    /// NOTE: There are other dead positions (e.g., locked pawn chains), this only covers the ones
+    /// that are down to material.
+    pub fn is_insufficient_material(&self) -> bool {
+        let heavy = COLORS.iter().any(|color| {
+            (self.pawns_for(color) | self.rooks_for(color) | self.queens_for(color)).is_nonempty()
+        });
+        if heavy { return false; }
+
+        let knights = self.knights_for(&Color::WHITE) | self.knights_for(&Color::BLACK);
+        let bishops = self.bishops_for(&Color::WHITE) | self.bishops_for(&Color::BLACK);
+        if (knights | bishops).count() <= 1 { return true; }
+
+        let mut square_colors = bishops.into_iter().map(|sq| (sq.rank() + sq.file()) % 2);
+        let first = square_colors.next();
+        knights.is_empty() && square_colors.all(|color| Some(color) == first)
+    }
+
     pub fn metadata(&self) -> PositionMetadata {
         self.inner.read().unwrap().metadata
     }
@@ -242,10 +327,15 @@ impl Position {
         // TODO: Ideally this is lazy, so we only update the board as we roll the associated
         // boardfamiliar forward.
         match self.atm.get(position_hash) {
-            Some(cached_inner) => {
+            Some(mut cached_inner) => {
                 // Atomic, TODO: Handle Result
                 tracing::trace!("Cache hit {:?} -> {:?}", position_hash, to_fen_position(&cached_inner));
-                _ = self.inner.replace(cached_inner.clone());
+                // The hash doesn't cover the clocks, so the cached ones are from whenever we were
+                // last here. The turn we just wrote has the right ones.
+                if let Some(Alteration::Inform(metadata)) = new_alterations.last() {
+                    cached_inner.metadata = *metadata;
+                }
+                _ = self.inner.replace(cached_inner);
             },
             None => {
                 tracing::trace!("Cache miss");
@@ -308,6 +398,10 @@ impl Position {
                 }
 
             }
+
+            // Anything past the head is from the turn we just unmade. Familiars read a little past
+            // the writehead, so it has to go or it'll leak into the zobrist.
+            tape.truncate();
         }
         tracing::trace!("Tape unlocked from write");
 
@@ -323,10 +417,17 @@ impl Position {
         // about. I think that might be easier once I've moved to the generic familiar system I've
         // been cooking.
         match self.atm.get(unmove_hash) {
-            Some(cached_inner) => {
+            Some(mut cached_inner) => {
                 tracing::trace!("Unmake cache hit {:?}", unmove_hash);
+                // As in `make`, the clocks come from the tape, here from the turn's `Assert`.
+                if let Some(metadata) = unmoves.iter().find_map(|alter| match alter {
+                    Alteration::Assert(metadata) => Some(*metadata),
+                    _ => None,
+                }) {
+                    cached_inner.metadata = metadata;
+                }
                 // Atomic, TODO: Handle Result
-                _ = self.inner.replace(cached_inner.clone());
+                _ = self.inner.replace(cached_inner);
             },
             None => {
                 tracing::trace!("Unmake cache miss");
@@ -334,6 +435,10 @@ impl Position {
                 let mut inner = self.inner.write().unwrap();
 
                 for alter in unmoves {
+                    // `Turn` clears the en passant file on the way forward, but the `Assert` we've
+                    // just restored already has the right one, so it mustn't be reapplied here.
+                    if matches!(alter, Alteration::Turn) { continue; }
+
                     inner.metadata.alter_mut(alter.inverse());
                     inner.board.alter_mut(alter.inverse());
                 }
@@ -651,7 +756,10 @@ impl Position {
     }
 
     fn slide_attacks_for(&self, piece: Piece , color: Color) -> Bitboard {
-        let blockers = self.all_blockers();
+        self.slide_attacks_through(piece, color, self.all_blockers())
+    }
+
+    fn slide_attacks_through(&self, piece: Piece, color: Color, blockers: Bitboard) -> Bitboard {
         self.find(|(_, occ)| { *occ == Occupant::Occupied(piece, color) })
             .into_iter()
             .map(|sq| { hazel_bitboard::pextboard::attacks_for(piece, sq, blockers) })
@@ -666,6 +774,61 @@ impl Position {
         self.their_rook_moves() |
         self.their_queen_moves()
     }
+
+    /// The squares our king may not step onto. Unlike `their_reach`, this counts squares their own
+    /// pieces sit on (so defended pieces can't be taken by the king), and looks _through_ our king,
+    /// so the king can't escape a slider by stepping backwards along the checking ray.
+    pub fn their_king_danger(&self) -> Bitboard {
+        let villain = self.villain();
+        let blockers = self.all_blockers() & !Bitboard::from(self.our_king());
+
+        let knights = self.knights_for(&villain)
+            .into_iter()
+            .map(|sq| { KNIGHT_MOVES[sq.index()] })
+            .fold(Bitboard::empty(), |acc, e| acc | e);
+
+        KING_ATTACKS[self.their_king().index()] |
+        self.their_pawn_attacks() |
+        knights |
+        self.slide_attacks_through(Piece::Bishop, villain, blockers) |
+        self.slide_attacks_through(Piece::Rook, villain, blockers) |
+        self.slide_attacks_through(Piece::Queen, villain, blockers)
+    }
+
+    // ### ATTACKERS ### //
+
+    pub fn kings_for(&self, color: &Color) -> Bitboard {
+        self.find(|(_sq, occ)| {
+            *occ == Occupant::Occupied(Piece::King, *color)
+        })
+    }
+
+    /// All the pieces of `color` which attack `square`, treating `blockers` as the occupied
+    /// squares. Passing something other than `all_blockers` lets you ask 'what if this piece
+    /// weren't here' questions.
+    pub fn attackers_of(&self, square: Square, color: &Color, blockers: Bitboard) -> Bitboard {
+        use hazel_bitboard::pextboard::attacks_for;
+
+        // A pawn attacks `square` if it sits where a pawn of the _other_ color on `square` would
+        // attack.
+        let behind = Bitboard::from(square).shift((!*color).pawn_direction());
+        let pawns = (behind.shift(Direction::E) | behind.shift(Direction::W)) & self.pawns_for(color);
+
+        let queens = self.queens_for(color);
+        let diagonals = attacks_for(Piece::Bishop, square, blockers) & (self.bishops_for(color) | queens);
+        let orthogonals = attacks_for(Piece::Rook, square, blockers) & (self.rooks_for(color) | queens);
+
+        pawns |
+        (KNIGHT_MOVES[square.index()] & self.knights_for(color)) |
+        (KING_ATTACKS[square.index()] & self.kings_for(color)) |
+        diagonals |
+        orthogonals
+    }
+
+    /// The pieces currently giving check to our king.
+    pub fn checkers(&self) -> Bitboard {
+        self.attackers_of(self.our_king(), &self.villain(), self.all_blockers())
+    }
 }
 
 
@@ -741,6 +904,156 @@ mod tests {
 
             assert_eq!(p_prior, p);
         }
+
+        #[test]
+        fn unmake_restores_en_passant() {
+            let mut p = Position::with_moves(BEN::start_position(), vec![Move::new(E2, E4, MoveType::DOUBLE_PAWN)]);
+            let prior = p.metadata();
+
+            p.make(Move::new(G8, F6, MoveType::QUIET));
+            p.unmake();
+
+            assert_eq!(p.metadata(), prior);
+        }
+
+        #[test]
+        fn unmade_turns_do_not_leak_into_the_zobrist() {
+            // A capture writes more alterations than a quiet move, so unmaking one and making the
+            // other leaves the tail end of the capture sitting past the writehead.
+            let start = BEN::new("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1");
+            let mut p = Position::new(start);
+
+            p.make(Move::new(E4, D5, MoveType::CAPTURE));
+            p.unmake();
+            p.make(Move::new(E4, E5, MoveType::QUIET));
+
+            let fresh = Position::with_moves(start, vec![Move::new(E4, E5, MoveType::QUIET)]);
+            assert_eq!(p.zobrist(), fresh.zobrist());
+        }
+
+        #[test]
+        fn cache_hits_keep_the_clocks() {
+            // Back to the start position, which is already cached with both clocks at the start.
+            let mut p = Position::with_moves(BEN::start_position(), vec![
+                Move::new(G1, F3, MoveType::QUIET), Move::new(G8, F6, MoveType::QUIET),
+                Move::new(F3, G1, MoveType::QUIET), Move::new(F6, G8, MoveType::QUIET),
+            ]);
+            assert_eq!(p.metadata().halfmove_clock, 4);
+            assert_eq!(p.metadata().fullmove_number, 3);
+
+            p.unmake();
+            assert_eq!(p.metadata().halfmove_clock, 3);
+            assert_eq!(p.metadata().fullmove_number, 2);
+        }
+    }
+
+    mod draws {
+        use super::*;
+
+        fn shuffle() -> Vec<Move> {
+            vec![
+                Move::new(G1, F3, MoveType::QUIET), Move::new(G8, F6, MoveType::QUIET),
+                Move::new(F3, G1, MoveType::QUIET), Move::new(F6, G8, MoveType::QUIET),
+            ]
+        }
+
+        #[test]
+        fn history_goes_back_to_the_last_irreversible_move() {
+            let mut moves = vec![Move::new(E2, E4, MoveType::DOUBLE_PAWN)];
+            moves.extend(shuffle());
+            let p = Position::with_moves(BEN::start_position(), moves);
+
+            let history = p.zobrist_history();
+            assert_eq!(history.len(), 5);
+            assert_eq!(history[0], Position::with_moves(BEN::start_position(), vec![Move::new(E2, E4, MoveType::DOUBLE_PAWN)]).zobrist().position);
+            assert_eq!(history[4], p.zobrist().position);
+        }
+
+        #[test]
+        fn repetitions() {
+            let mut p = Position::new(BEN::start_position());
+            assert!(!p.is_repetition());
+
+            for mov in shuffle() { p.make(mov); }
+            assert_eq!(p.repetitions(), 1);
+            assert!(p.is_repetition());
+            assert!(!p.is_threefold_repetition());
+
+            for mov in shuffle() { p.make(mov); }
+            assert_eq!(p.repetitions(), 2);
+            assert!(p.is_threefold_repetition());
+            assert!(p.is_draw_by_rule());
+
+            // Black to move with the knight on f6 has only come up once before.
+            p.unmake();
+            assert_eq!(p.repetitions(), 1);
+            assert!(!p.is_draw_by_rule());
+        }
+
+        #[test]
+        fn irreversible_moves_reset_repetitions() {
+            let mut moves = shuffle();
+            moves.extend(shuffle());
+            let mut p = Position::with_moves(BEN::start_position(), moves);
+            assert!(p.is_threefold_repetition());
+
+            p.make(Move::new(E2, E3, MoveType::QUIET));
+            p.make(Move::new(E7, E6, MoveType::QUIET));
+            assert!(!p.is_repetition());
+
+            // Only the positions since the pawn moves count.
+            for mov in shuffle() { p.make(mov); }
+            assert_eq!(p.repetitions(), 1);
+        }
+
+        #[test]
+        fn side_to_move_matters() {
+            // The pieces are back where they started, but it's black to move.
+            let p = Position::with_moves(BEN::new("4k3/8/8/8/8/8/8/R3K3 w - - 0 1"), vec![
+                Move::new(A1, A3, MoveType::QUIET), Move::new(E8, D8, MoveType::QUIET),
+                Move::new(A3, A2, MoveType::QUIET), Move::new(D8, E8, MoveType::QUIET),
+                Move::new(A2, A1, MoveType::QUIET),
+            ]);
+            assert!(!p.is_repetition());
+        }
+
+        #[test]
+        fn insufficient_material() {
+            for fen in [
+                "4k3/8/8/8/8/8/8/4K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/2N1K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/2B1K3 b - - 0 1",
+                // Bishops on the same color, however many.
+                "4k3/8/8/8/8/8/8/2B1K1B1 w - - 0 1",
+                "2b1k3/8/8/8/8/8/8/4KB2 w - - 0 1",
+            ] {
+                assert!(Position::new(BEN::new(fen)).is_insufficient_material(), "{fen}");
+            }
+
+            for fen in [
+                "4k3/8/8/8/8/8/4P3/4K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/R3K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1",
+                "4k3/8/8/8/8/8/8/1NB1K3 w - - 0 1",
+                // Opposite colored bishops can still mate, with help.
+                "4kb2/8/8/8/8/8/8/4KB2 w - - 0 1",
+                "4k3/8/8/8/8/8/8/2BBK3 w - - 0 1",
+            ] {
+                assert!(!Position::new(BEN::new(fen)).is_insufficient_material(), "{fen}");
+            }
+        }
+
+        #[test]
+        fn fifty_moves() {
+            let p = Position::new(BEN::new("4k3/8/8/8/8/8/8/R3K3 w - - 99 80"));
+            assert!(!p.is_fifty_move_draw());
+
+            let p = Position::with_moves(BEN::new("4k3/8/8/8/8/8/8/R3K3 w - - 99 80"), vec![Move::new(A1, A2, MoveType::QUIET)]);
+            assert!(p.is_fifty_move_draw());
+            assert!(p.is_draw_by_rule());
+            // The clock goes back further than the tape, that's fine.
+            assert_eq!(p.zobrist_history().len(), 2);
+        }
     }
 
     mod gamestate {
@@ -882,6 +1195,42 @@ mod tests {
         }
     }
 
+    mod attackers {
+        use super::*;
+
+        #[test]
+        fn checkers_finds_every_checking_piece() {
+            let pos = Position::new(BEN::new("4k3/8/8/8/1b6/5n2/8/4K2r w - - 0 1"));
+            assert_eq!(pos.checkers(), bitboard!(B4, F3, H1));
+        }
+
+        #[test]
+        fn checkers_ignores_blocked_sliders() {
+            let pos = Position::new(BEN::new("4k3/8/8/8/1b6/2P5/8/4K3 w - - 0 1"));
+            assert_eq!(pos.checkers(), Bitboard::empty());
+        }
+
+        #[test]
+        fn attackers_of_finds_pawns_of_either_color() {
+            let pos = Position::new(BEN::new("4k3/8/8/2p1p3/3P4/8/8/4K3 w - - 0 1"));
+            assert_eq!(pos.attackers_of(D4, &Color::BLACK, pos.all_blockers()), bitboard!(C5, E5));
+            assert_eq!(pos.attackers_of(E5, &Color::WHITE, pos.all_blockers()), bitboard!(D4));
+        }
+
+        #[test]
+        fn king_danger_sees_through_our_king() {
+            let pos = Position::new(BEN::new("4k3/8/8/8/8/8/8/r3K3 w - - 0 1"));
+            assert!(pos.their_king_danger().is_set(F1));
+            assert!(!pos.their_reach().is_set(F1));
+        }
+
+        #[test]
+        fn king_danger_includes_defended_pieces() {
+            let pos = Position::new(BEN::new("4k3/8/8/8/8/1n6/3p4/4K3 w - - 0 1"));
+            assert!(pos.their_king_danger().is_set(D2));
+        }
+    }
+
     mod bishop {
         use super::*;
 
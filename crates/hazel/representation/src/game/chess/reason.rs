@@ -1,12 +1,103 @@
 use hazel_core::color::Color;
 
-#[derive(Debug, Copy, Clone, PartialEq)]
+/// Why a game ended.
+///
+/// Decisive results hold the color which *won*, whatever the reason, so `Resignation(WHITE)` is
+/// black resigning.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum Reason {
-    /// Checkmate by the given color
+    /// Won, for some reason we don't know, e.g. a PGN `1-0`
     Winner(Color),
-    /// Draw for any reason
+    /// Checkmate by the given color
+    Checkmate(Color),
+    /// The other color resigned
+    Resignation(Color),
+    /// The other color ran out of time
+    Timeout(Color),
+    /// Drawn, for some reason we don't know, e.g. a PGN `1/2-1/2`
+    Draw,
+    /// No legal moves, and not in check
     Stalemate,
+    ThreefoldRepetition,
+    FiftyMoveRule,
+    /// Nobody has enough left to mate with, see `Position::is_insufficient_material`
+    InsufficientMaterial,
+    /// Both players agreed to a draw
+    Agreement,
     /// Aborted for unspecified reason
     Aborted,
 }
 
+impl Reason {
+    pub fn winner(&self) -> Option<Color> {
+        match self {
+            Reason::Winner(color) |
+            Reason::Checkmate(color) |
+            Reason::Resignation(color) |
+            Reason::Timeout(color) => Some(*color),
+            _ => None,
+        }
+    }
+
+    pub fn is_draw(&self) -> bool {
+        matches!(self,
+            Reason::Draw |
+            Reason::Stalemate |
+            Reason::ThreefoldRepetition |
+            Reason::FiftyMoveRule |
+            Reason::InsufficientMaterial |
+            Reason::Agreement
+        )
+    }
+
+    /// The PGN result token, `1-0`, `0-1`, `1/2-1/2`, or `*` if there's no result.
+    pub fn to_pgn(&self) -> &'static str {
+        match self.winner() {
+            Some(Color::WHITE) => "1-0",
+            Some(Color::BLACK) => "0-1",
+            None if self.is_draw() => "1/2-1/2",
+            None => "*",
+        }
+    }
+
+    /// The reverse of `to_pgn`. PGN doesn't say why, so this is only ever `Winner`, `Draw` or
+    /// `Aborted`.
+    pub fn from_pgn(result: &str) -> Option<Self> {
+        match result {
+            "1-0" => Some(Reason::Winner(Color::WHITE)),
+            "0-1" => Some(Reason::Winner(Color::BLACK)),
+            "1/2-1/2" => Some(Reason::Draw),
+            "*" => Some(Reason::Aborted),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pgn_results() {
+        assert_eq!(Reason::Checkmate(Color::WHITE).to_pgn(), "1-0");
+        assert_eq!(Reason::Resignation(Color::BLACK).to_pgn(), "0-1");
+        assert_eq!(Reason::Timeout(Color::WHITE).to_pgn(), "1-0");
+        assert_eq!(Reason::Agreement.to_pgn(), "1/2-1/2");
+        assert_eq!(Reason::InsufficientMaterial.to_pgn(), "1/2-1/2");
+        assert_eq!(Reason::Aborted.to_pgn(), "*");
+    }
+
+    #[test]
+    fn round_trips_through_pgn() {
+        for reason in [
+            Reason::Checkmate(Color::BLACK), Reason::Timeout(Color::WHITE), Reason::Stalemate,
+            Reason::ThreefoldRepetition, Reason::FiftyMoveRule, Reason::Aborted,
+        ] {
+            let result = Reason::from_pgn(reason.to_pgn()).unwrap();
+            assert_eq!(result.to_pgn(), reason.to_pgn());
+            assert_eq!(result.winner(), reason.winner());
+        }
+
+        assert_eq!(Reason::from_pgn("2-0"), None);
+    }
+}
@@ -62,6 +62,35 @@ impl Variation {
         self
     }
 
+    /// Why the game ended, from the last (committed) `halt`, if there was one.
+    pub fn result(&self) -> Option<Reason> {
+        (0..self.log.len()).rev().find_map(|idx| match self.log.get(idx) {
+            Some(Action::Halt(reason)) => Some(*reason),
+            _ => None,
+        })
+    }
+
+    /// The position the (last) game starts from, and the moves played from there, skipping over
+    /// any variations.
+    pub fn mainline(&self) -> (BEN, Vec<Move>) {
+        let mut start = BEN::start_position();
+        let mut moves = vec![];
+        let mut depth = 0usize;
+
+        for idx in 0..self.log.len() {
+            match self.log.get(idx) {
+                Some(Action::Variation(Delim::Start)) => depth += 1,
+                // An unmatched end (e.g., from a broken PGN) is ignored.
+                Some(Action::Variation(Delim::End)) => depth = depth.saturating_sub(1),
+                Some(Action::Setup(ben)) if depth == 0 => { start = *ben; moves.clear(); },
+                Some(Action::Make(mov)) if depth == 0 => moves.push(*mov),
+                _ => {}
+            }
+        }
+
+        (start, moves)
+    }
+
     pub fn setup(&mut self, ben: impl Into<BEN>) -> &mut Self {
         self.record(Action::Setup(ben.into()));
         self
@@ -151,6 +180,7 @@ mod tests {
     use game::delim::Delim;
 
     use crate::coup::rep::MoveType;
+    use hazel_core::color::Color;
     use hazel_core::square::*;
     use crate::game::chess::PositionMetadata;
     use crate::*;
@@ -176,6 +206,46 @@ mod tests {
         assert_eq!(actual_fen, BEN::new("rnbqkbnr/pppppppp/8/8/3P4/8/PPP1PPPP/RNBQKBNR b KQkq d3 0 1"));
     }
 
+    #[test]
+    fn result_is_the_last_halt() {
+        let mut game = Variation::default();
+        game.new_game()
+            .make(Move::new(D2, D4, MoveType::DOUBLE_PAWN))
+            .commit();
+        assert_eq!(game.result(), None);
+
+        game.halt(Reason::Resignation(Color::WHITE)).commit();
+        assert_eq!(game.result(), Some(Reason::Resignation(Color::WHITE)));
+        assert_eq!(game.result().unwrap().to_pgn(), "1-0");
+    }
+
+    #[test]
+    fn mainline_skips_variations() {
+        let mut game = Variation::default();
+        game.new_game()
+            .make(Move::new(E2, E4, MoveType::DOUBLE_PAWN))
+            .variation(|v| { v.make(Move::new(D2, D4, MoveType::DOUBLE_PAWN)); })
+            .make(Move::new(E7, E5, MoveType::DOUBLE_PAWN))
+            .commit();
+
+        assert_eq!(game.mainline(), (BEN::start_position(), vec![
+            Move::new(E2, E4, MoveType::DOUBLE_PAWN),
+            Move::new(E7, E5, MoveType::DOUBLE_PAWN),
+        ]));
+    }
+
+    #[test]
+    fn mainline_survives_an_unmatched_end() {
+        let mut game = Variation::default();
+        game.new_game()
+            .make(Move::new(E2, E4, MoveType::DOUBLE_PAWN))
+            .end_variation()
+            .make(Move::new(E7, E5, MoveType::DOUBLE_PAWN))
+            .commit();
+
+        assert_eq!(game.mainline().1.len(), 2);
+    }
+
     #[test]
     fn fen_correct_after_castling() {
         let mut game = Variation::default();
@@ -14,6 +14,7 @@ use std::{collections::HashMap, fmt::Debug, sync::Mutex};
 use tui_logger::{LevelFilter, TuiLoggerLevelOutput, TuiLoggerSmartWidget, TuiWidgetState};
 
 use crate::ui::widgets::tapereader::TapeReaderWidget;
+use crate::ui::widgets::variations::Variations;
 
 use super::widgets::{board::Board, fen::FEN, input::Input, output::Output};
 
@@ -28,6 +29,7 @@ pub struct UI<'a> {
     // UI
     mode: Mode,
     tapereader: TapeReaderWidget,
+    variations: Variations,
     tuiloggerstate: TuiWidgetState,
     // State
     tapereader_state: Option<Quintessence<TapeReaderState>>,
@@ -73,6 +75,7 @@ impl<'a> UI<'a> {
             engine,
             mode: Mode::Command,
             tapereader: TapeReaderWidget::default(),
+            variations: Variations::default(),
             tuiloggerstate: TuiWidgetState::new().set_default_display_level(LevelFilter::Trace),
             tapereader_state: None,
             current_ben: None,
@@ -111,6 +114,9 @@ impl<'a> UI<'a> {
                         KeyCode::Char('q') => {
                             self.set_flag("exit", true);
                         },
+                        KeyCode::Char('g') => {
+                            self.set_flag("go", true);
+                        },
 
                         KeyCode::Down => {
                             tracing::trace!("Advancing TapeReader");
@@ -155,9 +161,26 @@ impl<'a> UI<'a> {
         // TODO: This API kinda sucks, the getposition should send back something over a channel
         // instead of relying on a sort of psuedo-synchronous thing, race conditions everywhere
         // with this.
+        if self.check_flag("go") {
+            self.set_flag("go", false);
+            // The last search's lines are no use for this one, even the ones it doesn't replace.
+            self.variations.clear();
+            self.engine.send(Box::new(UCIMessage::Go(vec!["movetime".to_string(), "5000".to_string()]))).await;
+        }
+
         tracing::info!(target="hazel::ui::update", "Updating Position");
         self.engine.send(Box::new(GetPosition)).await;
-        match self.engine.read().await {
+        // Anything the engine says while searching comes through here too, so pick out the
+        // lines it's found on the way, and skip past the rest until we get the position.
+        let mut response = self.engine.read().await;
+        while let Some(HazelResponse::UCIResponse(message)) = &response {
+            if let UCIMessage::Info(info) = message {
+                self.variations.update(info);
+            }
+            response = self.engine.read().await;
+        }
+
+        match response {
             Some(HazelResponse::Position(Some(pos))) => {
                 tracing::debug!(target="hazel::ui::update", "Position is nonempty");
                 let mut fam = match &self.tapereader_state {
@@ -210,7 +233,18 @@ impl<'a> UI<'a> {
 
         let chunks = UPPER_LAYOUT.split(upper_section);
         let board_section = chunks[0];
-        let tapereader_section = chunks[1];
+        let mut tapereader_section = chunks[1];
+
+        // Only make room for the engine's lines once it has some.
+        if !self.variations.is_empty() {
+            let lines = self.variations.lines().len() as u16;
+            let chunks = Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints([Constraint::Min(1), Constraint::Length(lines + 2)].as_ref())
+                .split(tapereader_section);
+            tapereader_section = chunks[0];
+            Widget::render(&self.variations, chunks[1], frame.buffer_mut());
+        }
 
         let chunks = BOARD_SECTION_LAYOUT.split(board_section);
         let _board_header = chunks[0];
@@ -0,0 +1,126 @@
+use std::collections::BTreeMap;
+
+use hazel_engine::uci::{UCIInfo, UCIScore};
+use ratatui::prelude::*;
+use ratatui::widgets::{Block, Borders};
+
+/// The engine's best lines, from its `info` output, one row per `multipv` line, best first.
+///
+/// Engines that don't do MultiPV don't send `multipv` at all, so that's treated as line 1.
+#[derive(Debug, Default)]
+pub struct Variations {
+    lines: BTreeMap<usize, Variation>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Variation {
+    depth: Option<usize>,
+    score: Option<UCIScore>,
+    pv: String,
+}
+
+impl Variations {
+    /// Lines without a `pv` (e.g., `info string ...`) are ignored.
+    pub fn update(&mut self, info: &UCIInfo) {
+        if info.pv.is_empty() { return; }
+
+        let pv = info.pv.iter().map(|mov| mov.to_uci()).collect::<Vec<String>>().join(" ");
+        self.lines.insert(info.multipv.unwrap_or(1), Variation { depth: info.depth, score: info.score, pv });
+    }
+
+    /// Forget the old lines, e.g. when a new search starts.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        self.lines.iter().map(|(k, line)| {
+            let depth = line.depth.map_or("?".to_string(), |depth| depth.to_string());
+            format!("{}. {:>6} d{:<2} {}", k, format_score(line.score), depth, line.pv)
+        }).collect()
+    }
+}
+
+// Centipawns as pawns, `+0.35`, and mates as `#-2`.
+fn format_score(score: Option<UCIScore>) -> String {
+    match score {
+        Some(UCIScore::Centipawns(cp)) => format!("{:+.2}", cp as f32 / 100.0),
+        Some(UCIScore::Mate(moves)) => format!("#{}", moves),
+        None => "?".to_string(),
+    }
+}
+
+impl Widget for &Variations {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::White).bg(Color::Black));
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        for (y, line) in (inner.top()..inner.bottom()).zip(self.lines()) {
+            buf.set_stringn(inner.left(), y, line, inner.width as usize, Style::default().fg(Color::White).bg(Color::Black));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn info(line: &str) -> UCIInfo {
+        line.parse().unwrap()
+    }
+
+    #[test]
+    fn keeps_the_latest_of_each_line() {
+        let mut variations = Variations::default();
+        variations.update(&info("depth 1 multipv 2 score cp -12 nodes 40 pv d2d4"));
+        variations.update(&info("depth 1 multipv 1 score cp 35 nodes 20 pv e2e4"));
+        variations.update(&info("depth 2 multipv 1 score cp 20 nodes 90 pv e2e4 e7e5"));
+
+        assert_eq!(variations.lines(), vec![
+            "1.  +0.20 d2  e2e4 e7e5",
+            "2.  -0.12 d1  d2d4",
+        ]);
+    }
+
+    #[test]
+    fn single_line_engines_and_mates() {
+        let mut variations = Variations::default();
+        variations.update(&info("string hello there"));
+        assert!(variations.is_empty());
+
+        variations.update(&info("depth 3 score mate -2 pv e1e2 a1a2"));
+        assert_eq!(variations.lines(), vec!["1.    #-2 d3  e1e2 a1a2"]);
+
+        variations.clear();
+        assert!(variations.is_empty());
+    }
+
+    #[test]
+    fn renders_each_line() {
+        let rect = Rect::new(0, 0, 24, 4);
+        let mut buffer = Buffer::empty(rect);
+        buffer.set_style(rect, Style::default().fg(Color::White).bg(Color::Black));
+
+        let mut variations = Variations::default();
+        variations.update(&info("depth 1 multipv 1 score cp 35 pv e2e4"));
+        variations.update(&info("depth 1 multipv 2 score cp 30 pv d2d4 d7d5 c2c4"));
+        variations.render(rect, &mut buffer);
+
+        let mut expected = Buffer::with_lines(vec![
+            "┌──────────────────────┐",
+            "│1.  +0.35 d1  e2e4    │",
+            "│2.  +0.30 d1  d2d4 d7d│",
+            "└──────────────────────┘",
+        ]);
+        expected.set_style(rect, Style::default().fg(Color::White).bg(Color::Black));
+
+        assert_eq!(buffer, expected);
+    }
+}
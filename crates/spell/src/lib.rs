@@ -246,6 +246,11 @@ impl Tape {
             self.head -= 1;
         }
     }
+
+    /// Drop everything at or after the head, as if it had never been written.
+    pub fn truncate(&mut self) {
+        self.hwm = self.head;
+    }
 }
 
 impl Deref for Tape {
@@ -382,6 +387,19 @@ mod tests {
         assert!(tape.at_eot());
     }
 
+    #[test]
+    fn truncate() {
+        let mut tape = Tape::default();
+        let alteration = Alteration::place(D4, Occupant::white_pawn());
+        tape.write_all(&[alteration, alteration]);
+        tape.step_backward();
+        tape.truncate();
+
+        assert!(tape.at_eot());
+        assert_eq!(tape.read_address(0), alteration);
+        assert_eq!(tape.read_address(1), Alteration::Noop);
+    }
+
     #[test]
     fn tape_can_read_addresses() {
         let mut tape = Tape::default();
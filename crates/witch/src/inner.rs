@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use tokio::sync::{mpsc, broadcast};
 
 use super::MessageForWitch;
@@ -45,6 +47,24 @@ where S : Default + Send + Clone + 'static,
         let _ = self.outbox.send(v);
     }
 
+    /// A sender back into this Witch's own inbox, for work happening off the actor (e.g., on
+    /// another thread) to report back with. From outside of an async context, use `blocking_send`.
+    pub fn sase(&self) -> mpsc::Sender<MessageForWitch<BUF_SIZE, S, R>> {
+        self.sase.clone()
+    }
+
+    /// Sends `msg` to ourselves once `delay` has passed, without holding up the actor in the
+    /// meantime.
+    pub fn send_after(&self, delay: Duration, msg: MessageForWitch<BUF_SIZE, S, R>) {
+        let sase = self.sase.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            if let Err(e) = sase.send(msg).await {
+                tracing::error!("Error sending delayed message to Witch: {:?}", e);
+            }
+        });
+    }
+
     // FIXME: Technically this duplicates WitchHandle#send, but IDK if I should rely on the extra
     // hop or just eat the cost of the duplication.
     pub async fn send(&self, msg: MessageForWitch<BUF_SIZE, S, R>) {
@@ -124,4 +124,59 @@ mod tests {
         }
 
     }
+
+    // Messages can schedule more messages, or hand out a way back in to work done elsewhere.
+    mod self_addressed_messages {
+        use std::time::Duration;
+
+        use super::*;
+
+        struct Write(i32);
+        #[async_trait::async_trait]
+        impl MessageFor<Witch<10, i32, i32>> for Write {
+            async fn run(&self, witch: &mut Witch<10, i32, i32>) {
+                witch.write(self.0);
+            }
+        }
+
+        struct Later;
+        #[async_trait::async_trait]
+        impl MessageFor<Witch<10, i32, i32>> for Later {
+            async fn run(&self, witch: &mut Witch<10, i32, i32>) {
+                witch.send_after(Duration::from_millis(20), Box::new(Write(2)));
+                witch.write(1);
+            }
+        }
+
+        struct OffThread;
+        #[async_trait::async_trait]
+        impl MessageFor<Witch<10, i32, i32>> for OffThread {
+            async fn run(&self, witch: &mut Witch<10, i32, i32>) {
+                let sase = witch.sase();
+                std::thread::spawn(move || {
+                    sase.blocking_send(Box::new(Write(42))).unwrap();
+                });
+            }
+        }
+
+        #[tokio::test]
+        async fn send_after_does_not_block_the_witch() {
+            let w = WitchHandle::<10, i32, i32>::new().await;
+
+            w.send(Box::new(Later)).await;
+            w.send(Box::new(Write(3))).await;
+
+            assert_eq!(w.read().await, Some(1));
+            assert_eq!(w.read().await, Some(3));
+            assert_eq!(w.read().await, Some(2));
+        }
+
+        #[tokio::test]
+        async fn sase_works_from_other_threads() {
+            let w = WitchHandle::<10, i32, i32>::new().await;
+
+            w.send(Box::new(OffThread)).await;
+            assert_eq!(w.read().await, Some(42));
+        }
+    }
 }
@@ -41,7 +41,7 @@ lazy_static::lazy_static! {
     // NOTE: Applies to both mirrors of the position.
     pub static ref POS4_MIRROR_COUNTS : Vec<usize> = vec![6, 264, 9467, 422333, 15833292, 706045033];
     pub static ref POS5_BUGCATCHER_COUNTS : Vec<usize> = vec![44, 1486, 62379, 2103487, 89941194];
-    pub static ref POS6_STEVEN_COUNTS : Vec<usize> = vec![1, 46, 2079, 89890, 3894594, 164075551, 6923051137, 287188994746, 11923589843526, 490154852788714];
+    pub static ref POS6_STEVEN_COUNTS : Vec<usize> = vec![46, 2079, 89890, 3894594, 164075551, 6923051137, 287188994746, 11923589843526, 490154852788714];
 
     pub static ref POS2_KIWIPETE_MOVES : Vec<Move> = vec![
         Move::new(A2, A3, MoveType::QUIET),
@@ -0,0 +1,137 @@
+mod fixtures;
+
+use hazel_core::ben::BEN;
+use hazel_generator::{MoveGenerator, PerftTable, DEFAULT_PERFT_TABLE_MB};
+use hazel_representation::game::position::Position;
+
+use fixtures::*;
+
+// NOTE: These only go as deep as is comfortable in a debug build, the deeper counts are there in
+// the fixtures if you're chasing something.
+
+fn perft(fen: &str, depth: usize) -> usize {
+    MoveGenerator::new().perft(depth, &mut Position::new(BEN::new(fen)))
+}
+
+// For the deeper sweeps, split the root across threads and share a perft table.
+fn parallel_perft(fen: &str, depth: usize) -> usize {
+    let table = PerftTable::new(DEFAULT_PERFT_TABLE_MB);
+    MoveGenerator::new().parallel_perft(depth, &Position::new(BEN::new(fen)), 4, Some(&table))
+}
+
+mod pos3_krp_endgame {
+    use super::*;
+
+    #[test]
+    fn depth_1() {
+        assert_eq!(perft(POS3_KRP_ENDGAME_FEN, 1), POS3_KRP_ENDGAME_COUNTS[0]);
+    }
+
+    #[test]
+    fn depth_2() {
+        assert_eq!(perft(POS3_KRP_ENDGAME_FEN, 2), POS3_KRP_ENDGAME_COUNTS[1]);
+    }
+
+    #[test]
+    fn depth_3() {
+        assert_eq!(perft(POS3_KRP_ENDGAME_FEN, 3), POS3_KRP_ENDGAME_COUNTS[2]);
+    }
+
+    #[test]
+    fn depth_4() {
+        assert_eq!(perft(POS3_KRP_ENDGAME_FEN, 4), POS3_KRP_ENDGAME_COUNTS[3]);
+    }
+}
+
+mod pos6_steven {
+    use super::*;
+
+    #[test]
+    fn depth_1() {
+        assert_eq!(perft(POS6_STEVEN_FEN, 1), POS6_STEVEN_COUNTS[0]);
+    }
+
+    #[test]
+    fn depth_2() {
+        assert_eq!(perft(POS6_STEVEN_FEN, 2), POS6_STEVEN_COUNTS[1]);
+    }
+
+    #[test]
+    fn depth_3() {
+        assert_eq!(perft(POS6_STEVEN_FEN, 3), POS6_STEVEN_COUNTS[2]);
+    }
+}
+
+mod pos2_kiwipete {
+    use super::*;
+
+    #[test]
+    fn depth_1() {
+        assert_eq!(perft(POS2_KIWIPETE_FEN, 1), POS2_KIWIPETE_PERFT_COUNTS[0]);
+    }
+
+    #[test]
+    fn depth_2() {
+        assert_eq!(perft(POS2_KIWIPETE_FEN, 2), POS2_KIWIPETE_PERFT_COUNTS[1]);
+    }
+
+    #[test]
+    fn depth_3() {
+        assert_eq!(perft(POS2_KIWIPETE_FEN, 3), POS2_KIWIPETE_PERFT_COUNTS[2]);
+    }
+}
+
+mod pos4_mirror {
+    use super::*;
+
+    #[test]
+    fn depth_1() {
+        assert_eq!(perft(POS4_MIRROR_1_FEN, 1), POS4_MIRROR_COUNTS[0]);
+        assert_eq!(perft(POS4_MIRROR_2_FEN, 1), POS4_MIRROR_COUNTS[0]);
+    }
+
+    #[test]
+    fn depth_2() {
+        assert_eq!(perft(POS4_MIRROR_1_FEN, 2), POS4_MIRROR_COUNTS[1]);
+        assert_eq!(perft(POS4_MIRROR_2_FEN, 2), POS4_MIRROR_COUNTS[1]);
+    }
+
+    #[test]
+    fn depth_3() {
+        assert_eq!(perft(POS4_MIRROR_1_FEN, 3), POS4_MIRROR_COUNTS[2]);
+        assert_eq!(perft(POS4_MIRROR_2_FEN, 3), POS4_MIRROR_COUNTS[2]);
+    }
+}
+
+mod pos5_bugcatcher {
+    use super::*;
+
+    #[test]
+    fn depth_1() {
+        assert_eq!(perft(POS5_BUGCATCHER_FEN, 1), POS5_BUGCATCHER_COUNTS[0]);
+    }
+
+    #[test]
+    fn depth_2() {
+        assert_eq!(perft(POS5_BUGCATCHER_FEN, 2), POS5_BUGCATCHER_COUNTS[1]);
+    }
+
+    #[test]
+    fn depth_3() {
+        assert_eq!(perft(POS5_BUGCATCHER_FEN, 3), POS5_BUGCATCHER_COUNTS[2]);
+    }
+}
+
+mod parallel {
+    use super::*;
+
+    #[test]
+    fn pos3_krp_endgame_depth_4() {
+        assert_eq!(parallel_perft(POS3_KRP_ENDGAME_FEN, 4), POS3_KRP_ENDGAME_COUNTS[3]);
+    }
+
+    #[test]
+    fn pos6_steven_depth_3() {
+        assert_eq!(parallel_perft(POS6_STEVEN_FEN, 3), POS6_STEVEN_COUNTS[2]);
+    }
+}